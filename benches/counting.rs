@@ -0,0 +1,41 @@
+/*!
+    * Compares counting bigrams into a HashMap of strings with counting into the fixed size matrix
+ **/
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use bigram::{clean_names, count_bigrams, count_matrix, BigramModel};
+
+// how many times the bundled corpus is repeated for the large corpus
+const LARGE_REPEATS: usize = 20;
+
+fn bundled_names() -> Vec<String> {
+    let names: Vec<String> = include_str!("../files/names.txt").split('\n').map(String::from).collect();
+    clean_names(&names)
+}
+
+fn counting(c: &mut Criterion) {
+    let bundled = bundled_names();
+    let large: Vec<String> = bundled.iter().cycle().take(bundled.len() * LARGE_REPEATS).cloned().collect();
+
+    let mut group = c.benchmark_group("counting");
+    group.sample_size(20);
+    for (label, names) in [("bundled", &bundled), ("large", &large)] {
+        group.bench_with_input(BenchmarkId::new("hashmap", label), names, |b, names| {
+            b.iter(|| count_bigrams(black_box(names)))
+        });
+        // the HashMap counts still have to be parsed into a matrix before they can be used
+        group.bench_with_input(BenchmarkId::new("hashmap_to_model", label), names, |b, names| {
+            b.iter(|| BigramModel::from_string_counts(&count_bigrams(black_box(names)), 1.0))
+        });
+        group.bench_with_input(BenchmarkId::new("array", label), names, |b, names| {
+            b.iter(|| count_matrix(black_box(names)))
+        });
+        group.bench_with_input(BenchmarkId::new("array_to_model", label), names, |b, names| {
+            b.iter(|| BigramModel::from_counts(count_matrix(black_box(names)), 1.0))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, counting);
+criterion_main!(benches);
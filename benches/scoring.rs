@@ -0,0 +1,43 @@
+/*!
+    * Compares scoring names one after the other with the scoring of the eval module,
+    * which uses every core when built with `--features parallel`
+ **/
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use bigram::eval::{cross_entropy, log_likelihoods};
+use bigram::{clean_names, count_matrix, log_likelihood_of_word, log_likelihood_with, scored_bigrams, BigramModel, BoundaryMode};
+
+// how many times the bundled corpus is repeated, about 320k names
+const REPEATS: usize = 10;
+
+fn scoring(c: &mut Criterion) {
+    let bundled: Vec<String> = include_str!("../files/names.txt").split('\n').map(String::from).collect();
+    let bundled = clean_names(&bundled);
+    let model = BigramModel::from_counts(count_matrix(&bundled), 1.0);
+    let names: Vec<String> = bundled.iter().cycle().take(bundled.len() * REPEATS).cloned().collect();
+    let label = names.len().to_string();
+
+    let mut group = c.benchmark_group("scoring");
+    group.sample_size(10);
+    group.bench_with_input(BenchmarkId::new("sequential_log_likelihoods", &label), &names, |b, names| {
+        b.iter(|| names.iter().map(|name| log_likelihood_of_word(name, black_box(&model.matrix))).collect::<Vec<f64>>())
+    });
+    group.bench_with_input(BenchmarkId::new("log_likelihoods", &label), &names, |b, names| {
+        b.iter(|| log_likelihoods(names, black_box(&model.matrix)))
+    });
+    group.bench_with_input(BenchmarkId::new("sequential_cross_entropy", &label), &names, |b, names| {
+        b.iter(|| {
+            let (nll, bigrams) = names.iter().fold((0.0, 0), |(nll, bigrams), name| {
+                (nll - log_likelihood_with(name, black_box(&model.matrix), BoundaryMode::Inclusive), bigrams + scored_bigrams(name, BoundaryMode::Inclusive))
+            });
+            nll / bigrams as f64
+        })
+    });
+    group.bench_with_input(BenchmarkId::new("cross_entropy", &label), &names, |b, names| {
+        b.iter(|| cross_entropy(names, black_box(&model.matrix), BoundaryMode::Inclusive))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, scoring);
+criterion_main!(benches);
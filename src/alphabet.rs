@@ -0,0 +1,233 @@
+/*!
+    * The layout of the matrix, which rows and columns are boundaries and which are letters
+ **/
+
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
+use crate::{BigramError, ALPHABET_SIZE};
+
+// number of characters in a model with separate start and end tokens
+pub const SPLIT_ALPHABET_SIZE: usize = ALPHABET_SIZE + 1;
+// number of characters in a case sensitive model with one boundary, A-Z get rows of their own
+pub const CASED_ALPHABET_SIZE: usize = ALPHABET_SIZE + 26;
+
+/**
+    * The boundary tokens of a model
+    * With one token for both, it starts and ends names from index 0 and the matrix is 27 wide.
+    * With distinct tokens, the start keeps index 0 and the end gets index 27, so the model
+    * learns how names start separately from how they end and the matrix is 28 wide.
+    * Cleaned names always use dots, a dot at the front is the start and one at the back is the end.
+    * The tokens themselves are only used to show and read the boundaries.
+    * A case sensitive alphabet puts A-Z after a-z, which roughly doubles the matrix,
+    * so every upper case bigram needs far more training names to be estimated well.
+    * An alphabet with spaces adds a space after the letters, for names of several words.
+    * Its end always gets an index of its own, so its size never matches a layout without spaces.
+    * Excluded letters keep their index, so the layout and its size don't change, but nothing moves into or out of them
+    * and they look up like characters outside of the alphabet.
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alphabet {
+    pub start: char,
+    pub end: char,
+    // models saved before there was a choice are lower case only
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub spaces: bool,
+    // a bit per excluded letter, bit i - 1 for the letter at index i
+    #[serde(default)]
+    pub excluded: u64,
+}
+
+impl Default for Alphabet {
+    fn default() -> Alphabet {
+        Alphabet { start: '.', end: '.', case_sensitive: false, spaces: false, excluded: 0 }
+    }
+}
+
+impl Alphabet {
+    /**
+        * Function to make an alphabet with the given boundary tokens
+        * Fails if a token is a letter or a space, which would hide it in the names
+    **/
+    pub fn new(start: char, end: char) -> Result<Alphabet, BigramError> {
+        for token in [start, end] {
+            if token.is_ascii_alphabetic() || token == ' ' {
+                return Err(BigramError::InvalidCharacter(token));
+            }
+        }
+        Ok(Alphabet { start, end, ..Alphabet::default() })
+    }
+
+    /**
+        * Function to get the same boundaries with or without rows for the upper case letters
+    **/
+    pub fn with_case(self, case_sensitive: bool) -> Alphabet {
+        Alphabet { case_sensitive, ..self }
+    }
+
+    /**
+        * Function to get the same alphabet with or without the space between words
+    **/
+    pub fn with_spaces(self, spaces: bool) -> Alphabet {
+        Alphabet { spaces, ..self }
+    }
+
+    /**
+        * Function to get the same alphabet without some of its letters, on top of the ones already excluded
+        * Characters that aren't letters of the alphabet are ignored
+    **/
+    pub fn with_excluded(self, chars: &[char]) -> Alphabet {
+        let excluded = chars.iter()
+            .filter_map(|c| self.layout_index(*c))
+            .filter(|i| self.letters().contains(i))
+            .fold(self.excluded, |mask, i| mask | 1 << (i - 1));
+        Alphabet { excluded, ..self }
+    }
+
+    pub fn is_excluded(&self, index: usize) -> bool {
+        self.letters().contains(&index) && self.excluded & (1 << (index - 1)) != 0
+    }
+
+    /**
+        * Function to list the excluded letters in the order of the alphabet
+    **/
+    pub fn excluded_chars(&self) -> Vec<char> {
+        self.letters().filter(|i| self.is_excluded(*i)).map(|i| self.char_at(i)).collect()
+    }
+
+    /**
+        * Function to get the alphabet of a matrix from its size
+        * A matrix with its own end index gets the `^` and `$` tokens, a matrix with rows for A-Z is case sensitive
+    **/
+    pub fn for_size(size: usize) -> Alphabet {
+        let split = Alphabet { start: '^', end: '$', ..Alphabet::default() };
+        [split, Alphabet::default().with_case(true), split.with_case(true), split.with_spaces(true), split.with_case(true).with_spaces(true)]
+            .into_iter()
+            .find(|alphabet| alphabet.size() == size)
+            .unwrap_or_default()
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.start != self.end
+    }
+
+    /**
+        * Function to check whether the end has an index of its own instead of sharing the start's
+        * With spaces it always does, the tokens then only change how names are shown
+    **/
+    pub fn has_end_index(&self) -> bool {
+        self.is_split() || self.spaces
+    }
+
+    pub fn size(&self) -> usize {
+        self.letters().len() + 1 + self.spaces as usize + self.has_end_index() as usize
+    }
+
+    pub fn end_index(&self) -> usize {
+        if self.has_end_index() { self.size() - 1 } else { 0 }
+    }
+
+    /**
+        * Function to get the index of the space, which follows the letters
+    **/
+    pub fn space_index(&self) -> Option<usize> {
+        self.spaces.then_some(self.letters().end)
+    }
+
+    /**
+        * Function to get the indices of the letters, a-z followed by A-Z when case sensitive
+    **/
+    pub fn letters(&self) -> Range<usize> {
+        if self.case_sensitive { 1..CASED_ALPHABET_SIZE } else { 1..ALPHABET_SIZE }
+    }
+
+    /**
+        * Function to convert an index to its character, showing the boundaries as their tokens
+    **/
+    pub fn char_at(&self, index: usize) -> char {
+        match index {
+            0 => self.start,
+            i if i == self.end_index() => self.end,
+            i if Some(i) == self.space_index() => ' ',
+            i if i < ALPHABET_SIZE => (i as u8 + b'a' - 1) as char,
+            i => (i as u8 + b'A' - ALPHABET_SIZE as u8) as char,
+        }
+    }
+
+    /**
+        * Function to convert a letter or a boundary token to its index
+        * The dot is read as the shared boundary, or as the start when the tokens are split
+    **/
+    pub fn index_of(&self, c: char) -> Option<usize> {
+        match c {
+            c if c.is_ascii_alphabetic() || c == ' ' => self.letter_index(c),
+            c if c == self.start || c == '.' => Some(0),
+            c if c == self.end => Some(self.end_index()),
+            _ => None,
+        }
+    }
+
+    /**
+        * Function to convert a cleaned name to the index of each of its characters
+        * The leading dot is the start and the trailing dot the end, anything outside of the alphabet is None
+    **/
+    pub fn name_indices(&self, cleaned: &str) -> Vec<Option<usize>> {
+        let chars = cleaned.chars().collect::<Vec<char>>();
+        let last = chars.len().saturating_sub(1);
+        chars.iter().enumerate().map(|(i, c)| match c {
+            '.' if i == 0 => Some(0),
+            '.' if i == last => Some(self.end_index()),
+            c => self.letter_index(*c),
+        }).collect()
+    }
+
+    /**
+        * Function to get the index of a letter or the space, None for an excluded letter
+        * Upper case letters only have one when case sensitive, and the space when the alphabet has spaces
+    **/
+    fn letter_index(&self, c: char) -> Option<usize> {
+        self.layout_index(c).filter(|i| !self.is_excluded(*i))
+    }
+
+    /**
+        * Function to get the place of a letter or the space in the layout, excluded or not
+    **/
+    fn layout_index(&self, c: char) -> Option<usize> {
+        match c {
+            'a'..='z' => Some(c as usize - 'a' as usize + 1),
+            'A'..='Z' if self.case_sensitive => Some(c as usize - 'A' as usize + ALPHABET_SIZE),
+            ' ' => self.space_index(),
+            _ => None,
+        }
+    }
+
+    /**
+        * Function to check whether the matrix may ever move from one index to another
+        * Nothing moves from the start straight to the end, into the start, or out of the end.
+        * A space only ever sits between two words, so it never follows the start, a space, or comes before the end.
+        * Nothing moves into or out of an excluded letter
+    **/
+    pub fn is_allowed(&self, from: usize, to: usize) -> bool {
+        if self.is_excluded(from) || self.is_excluded(to) {
+            return false;
+        }
+        let end = self.end_index();
+        if from == 0 && to == end {
+            return false;
+        }
+        if let Some(space) = self.space_index() {
+            if (to == space && (from == 0 || from == space)) || (from == space && to == end) {
+                return false;
+            }
+        }
+        !self.has_end_index() || (to != 0 && from != end)
+    }
+
+    /**
+        * Function to show a cleaned name with the boundary tokens instead of dots
+    **/
+    pub fn display(&self, cleaned: &str) -> String {
+        format!("{}{}{}", self.start, cleaned.trim_matches('.'), self.end)
+    }
+}
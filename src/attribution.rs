@@ -0,0 +1,84 @@
+/*!
+    * Attribution of a name to the training names behind the counts of its bigrams
+ **/
+
+use std::collections::{HashMap, HashSet};
+use crate::Alphabet;
+
+/**
+    * A bigram of a target name with the training names it was counted from
+**/
+#[derive(Debug, Clone, PartialEq)]
+pub struct BigramSources {
+    pub previous: char,
+    pub next: char,
+    // distinct training names holding the bigram, in corpus order and without boundary dots
+    pub names: Vec<String>,
+}
+
+/**
+    * Function to find the training names that contributed each bigram of a cleaned target name
+    * A bigram with a character outside of the alphabet, or one no training name holds,
+    * was never counted and only has a probability through smoothing, so it has no names
+    * 1. Split the target into its bigrams, boundaries included, like the count matrix sees it
+    * 2. Go through the distinct training names once, adding each to every target bigram it holds
+**/
+pub fn bigram_sources(target: &str, training_names: &[String], alphabet: &Alphabet) -> Vec<BigramSources> {
+    let target_indices = alphabet.name_indices(target);
+    let bigrams: Vec<Option<(usize, usize)>> = target_indices.windows(2)
+        .map(|pair| Some((pair[0]?, pair[1]?)))
+        .collect();
+    let wanted: HashSet<(usize, usize)> = bigrams.iter().flatten().cloned().collect();
+
+    let mut holders: HashMap<(usize, usize), Vec<String>> = HashMap::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    for name in training_names {
+        if !seen.insert(name) {
+            continue;
+        }
+        let indices = alphabet.name_indices(name);
+        let held: HashSet<(usize, usize)> = indices.windows(2)
+            .filter_map(|pair| Some((pair[0]?, pair[1]?)))
+            .filter(|bigram| wanted.contains(bigram))
+            .collect();
+        for bigram in held {
+            holders.entry(bigram).or_default().push(name.trim_matches('.').to_string());
+        }
+    }
+
+    let labels: Vec<char> = target_indices.iter().zip(target.chars())
+        .map(|(index, c)| index.map_or(c, |i| alphabet.char_at(i)))
+        .collect();
+    labels.windows(2).zip(&bigrams)
+        .map(|(pair, bigram)| BigramSources {
+            previous: pair[0],
+            next: pair[1],
+            names: bigram.and_then(|bigram| holders.get(&bigram)).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/**
+    * Function to pick a small set of training names that together hold every bigram with sources
+    * Greedy set cover, repeatedly taking the name holding the most bigrams not covered yet,
+    * so the set is at most a small factor larger than the smallest one and often exactly it.
+    * A name that is itself in training covers all of its bigrams on its own.
+**/
+pub fn covering_names(sources: &[BigramSources]) -> Vec<String> {
+    let mut uncovered: Vec<&BigramSources> = sources.iter().filter(|source| !source.names.is_empty()).collect();
+    let mut cover = Vec::new();
+    while !uncovered.is_empty() {
+        // how many uncovered bigrams each name holds, and when it was first met so ties are deterministic
+        let mut counts: HashMap<&String, (usize, usize)> = HashMap::new();
+        for name in uncovered.iter().flat_map(|source| &source.names) {
+            let order = counts.len();
+            counts.entry(name).or_insert((order, 0)).1 += 1;
+        }
+        let Some((name, _)) = counts.into_iter().max_by(|(_, (a_order, a)), (_, (b_order, b))| a.cmp(b).then(b_order.cmp(a_order))) else {
+            break;
+        };
+        uncovered.retain(|source| !source.names.contains(name));
+        cover.push(name.clone());
+    }
+    cover
+}
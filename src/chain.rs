@@ -0,0 +1,156 @@
+/*!
+    * Analytic properties of the matrix as a Markov chain
+ **/
+
+use crate::Alphabet;
+
+// largest change in any probability between two power iterations that counts as converged
+const STATIONARY_TOLERANCE: f64 = 1e-12;
+// power iterations before giving up on converging
+const STATIONARY_MAX_ITERATIONS: usize = 100_000;
+
+/**
+    * Function to compute the expected number of characters of a generated name, spaces between words included
+    * The chain starts at the start boundary and is absorbed at the end boundary, the letters and the space are its
+    * transient states. With Q the transitions between them, the fundamental matrix N = (I - Q)^-1 holds the expected
+    * visits of each, so the expected length is the first step distribution times N times a vector of ones.
+    * Returns infinity if some letters can never reach the end, since the system has no solution then
+    * 1. Build I - Q over the letters and the space
+    * 2. Solve (I - Q) x = 1, x is the expected letters still to come from each letter, itself included
+    * 3. Weight x by the probability of each first letter
+**/
+pub fn expected_name_length(bigram_matrix: &[Vec<f64>]) -> f64 {
+    let alphabet = Alphabet::for_size(bigram_matrix.len());
+    let letters: Vec<usize> = alphabet.letters().chain(alphabet.space_index()).collect();
+    let n = letters.len();
+    // augmented rows of [I - Q | 1]
+    let mut system: Vec<Vec<f64>> = letters.iter().enumerate().map(|(i, from)| {
+        let mut row: Vec<f64> = letters.iter().map(|to| -bigram_matrix[*from][*to]).collect();
+        row[i] += 1.0;
+        row.push(1.0);
+        row
+    }).collect();
+    let Some(remaining) = solve(&mut system, n) else {
+        return f64::INFINITY;
+    };
+    letters.iter().zip(&remaining).map(|(letter, x)| bigram_matrix[0][*letter] * x).sum()
+}
+
+/**
+    * Function to compute the long-run character frequencies of the chain inside names, ignoring the boundaries
+    * The distribution is indexed like the matrix, with the boundaries at 0.
+    * 1. Take the rows and columns of the letters (and the space), renormalizing each row without the boundaries;
+    *    a letter that only ever ends a name moves to any letter uniformly
+    * 2. Make the chain lazy, staying put half of the time, which keeps its stationary distribution
+    *    but makes power iteration converge even when the chain is periodic
+    * 3. Iterate from the uniform distribution until no probability changes by more than the tolerance,
+    *    or the iteration cap is reached
+**/
+pub fn stationary_distribution(bigram_matrix: &[Vec<f64>]) -> Vec<f64> {
+    let alphabet = Alphabet::for_size(bigram_matrix.len());
+    let inner: Vec<usize> = alphabet.letters().chain(alphabet.space_index()).collect();
+    let n = inner.len();
+    let transitions: Vec<Vec<f64>> = inner.iter().map(|from| {
+        let row: Vec<f64> = inner.iter().map(|to| bigram_matrix[*from][*to]).collect();
+        let total: f64 = row.iter().sum();
+        if total > 0.0 {
+            row.iter().map(|p| p / total).collect()
+        } else {
+            vec![1.0 / n as f64; n]
+        }
+    }).collect();
+
+    let mut distribution = vec![1.0 / n as f64; n];
+    for _ in 0..STATIONARY_MAX_ITERATIONS {
+        let mut next: Vec<f64> = distribution.iter().map(|p| 0.5 * p).collect();
+        for (p, row) in distribution.iter().zip(&transitions) {
+            for (value, q) in next.iter_mut().zip(row) {
+                *value += 0.5 * p * q;
+            }
+        }
+        let change = next.iter().zip(&distribution).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+        distribution = next;
+        if change < STATIONARY_TOLERANCE {
+            break;
+        }
+    }
+
+    let mut stationary = vec![0.0; bigram_matrix.len()];
+    for (index, p) in inner.iter().zip(distribution) {
+        stationary[*index] = p;
+    }
+    stationary
+}
+
+/**
+    * Function to compute the entropy of the joint bigram distribution of the chain, in bits
+    * Every row is weighted by how often the chain is in its character, so P(a, b) = P(a) P(b | a) with P(a)
+    * the expected visits of a per name over all of the visits. The start is visited once a name, and the visits of the
+    * letters solve v = p + v Q, with p the first step probabilities and Q the letter to letter transitions.
+    * Returns infinity if some letters can never reach the end, like expected_name_length
+**/
+pub fn joint_bigram_entropy(bigram_matrix: &[Vec<f64>]) -> f64 {
+    let alphabet = Alphabet::for_size(bigram_matrix.len());
+    let inner: Vec<usize> = alphabet.letters().chain(alphabet.space_index()).collect();
+    let n = inner.len();
+    // augmented rows of [(I - Q)^T | p], one per character moved into
+    let mut system: Vec<Vec<f64>> = inner.iter().enumerate().map(|(j, to)| {
+        let mut row: Vec<f64> = inner.iter().map(|from| -bigram_matrix[*from][*to]).collect();
+        row[j] += 1.0;
+        row.push(bigram_matrix[0][*to]);
+        row
+    }).collect();
+    let Some(visits) = solve(&mut system, n) else {
+        return f64::INFINITY;
+    };
+    let total = 1.0 + visits.iter().sum::<f64>();
+    let weights = std::iter::once((0, 1.0)).chain(inner.iter().cloned().zip(visits));
+    -weights
+        .flat_map(|(from, visits)| bigram_matrix[from].iter().map(move |p| visits / total * p))
+        .filter(|p| *p > 0.0)
+        .map(|p| p * p.log2())
+        .sum::<f64>()
+}
+
+/**
+    * Function to get the effective number of bigrams, 2 to the joint bigram entropy
+    * This is how many equally likely transitions would spread the chain as much as the matrix does
+**/
+pub fn effective_bigrams(bigram_matrix: &[Vec<f64>]) -> f64 {
+    joint_bigram_entropy(bigram_matrix).exp2()
+}
+
+/**
+    * Function to solve an augmented n x (n + 1) system with Gaussian elimination and partial pivoting
+    * Returns None if the system is singular
+**/
+fn solve(system: &mut [Vec<f64>], n: usize) -> Option<Vec<f64>> {
+    const SINGULAR: f64 = 1e-12;
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| system[*a][column].abs().total_cmp(&system[*b][column].abs()))?;
+        if system[pivot][column].abs() < SINGULAR {
+            return None;
+        }
+        system.swap(column, pivot);
+        let (done, rest) = system.split_at_mut(column + 1);
+        let pivot_row = &done[column];
+        for row in rest.iter_mut() {
+            let factor = row[column] / pivot_row[column];
+            if factor != 0.0 {
+                for (value, above) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                    *value -= factor * above;
+                }
+            }
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|k| system[row][k] * solution[k]).sum();
+        solution[row] = (system[row][n] - known) / system[row][row];
+    }
+    // expected visits can't be negative, a negative answer means the chain never ends
+    if solution.iter().any(|x| !x.is_finite() || *x < 0.0) {
+        return None;
+    }
+    Some(solution)
+}
@@ -0,0 +1,33 @@
+use std::error::Error;
+use std::fmt;
+
+/**
+    * Errors returned by the bigram model
+**/
+#[derive(Debug, Clone, PartialEq)]
+pub enum BigramError {
+    // a character that has no row or column in the matrix
+    InvalidCharacter(char),
+    // reading or writing a file failed
+    Io(String),
+    // a saved model could not be read back
+    InvalidModel(String),
+    // models can't be mixed with the given weights
+    InvalidMixture(String),
+    // every name of the corpus was empty or filtered out
+    EmptyCorpus,
+}
+
+impl fmt::Display for BigramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BigramError::InvalidCharacter(c) => write!(f, "character {:?} is not in the model alphabet", c),
+            BigramError::Io(message) => write!(f, "{}", message),
+            BigramError::InvalidModel(message) => write!(f, "invalid model file {}", message),
+            BigramError::InvalidMixture(message) => write!(f, "can't mix the models: {}", message),
+            BigramError::EmptyCorpus => write!(f, "no trainable names found, every line was empty or filtered out"),
+        }
+    }
+}
+
+impl Error for BigramError {}
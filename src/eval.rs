@@ -0,0 +1,196 @@
+/*!
+    * Held-out evaluation of a model on a corpus
+ **/
+
+use std::collections::HashSet;
+use std::f64::consts::LN_2;
+use rand::Rng;
+use rand::seq::SliceRandom;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use crate::{log_likelihood_of_word, score_word_with, BigramModel, BoundaryMode};
+
+/**
+    * Function to compute the cross entropy of a corpus under the model, in nats per character
+    * This is the total negative log likelihood divided by the number of scored bigrams,
+    * so with BoundaryMode::Interior the boundary transitions are left out of both
+    * Returns None if no bigram was scored, like for no names, rather than a perfect score
+**/
+pub fn cross_entropy(names: &[String], bigram_matrix: &[Vec<f64>], mode: BoundaryMode) -> Option<f64> {
+    let (nll, bigrams) = total_score(names, bigram_matrix, mode);
+    if bigrams == 0 {
+        return None;
+    }
+    Some(nll / bigrams as f64)
+}
+
+/**
+    * Function to sum the negative log likelihood and the scored bigrams of every name
+    * The matrix is only read, so with the parallel feature the names are scored on every core
+**/
+#[cfg(feature = "parallel")]
+fn total_score(names: &[String], bigram_matrix: &[Vec<f64>], mode: BoundaryMode) -> (f64, usize) {
+    names.par_iter()
+        .map(|name| {
+            let score = score_word_with(name, bigram_matrix, mode);
+            (-score.log_probability, score.bigrams)
+        })
+        .reduce(|| (0.0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+}
+
+#[cfg(not(feature = "parallel"))]
+fn total_score(names: &[String], bigram_matrix: &[Vec<f64>], mode: BoundaryMode) -> (f64, usize) {
+    names.iter().fold((0.0, 0), |(nll, bigrams), name| {
+        let score = score_word_with(name, bigram_matrix, mode);
+        (nll - score.log_probability, bigrams + score.bigrams)
+    })
+}
+
+/**
+    * Function to compute the log likelihood of every name, in the order of the names
+    * With the parallel feature the names are scored on every core
+**/
+#[cfg(feature = "parallel")]
+pub fn log_likelihoods(names: &[String], bigram_matrix: &[Vec<f64>]) -> Vec<f64> {
+    names.par_iter().map(|name| log_likelihood_of_word(name, bigram_matrix)).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn log_likelihoods(names: &[String], bigram_matrix: &[Vec<f64>]) -> Vec<f64> {
+    names.iter().map(|name| log_likelihood_of_word(name, bigram_matrix)).collect()
+}
+
+/**
+    * Function to compute the bits per character of a corpus under the model
+    * The cross entropy in base 2, the number to compare against published baselines
+    * Returns None if no bigram was scored, like cross_entropy
+**/
+pub fn bits_per_character(names: &[String], bigram_matrix: &[Vec<f64>], mode: BoundaryMode) -> Option<f64> {
+    cross_entropy(names, bigram_matrix, mode).map(|entropy| entropy / LN_2)
+}
+
+/**
+    * Function to compute the per character perplexity of a corpus under the model
+    * e to the cross entropy in nats, which is the same as 2 to the bits per character
+    * Returns None if no bigram was scored, like cross_entropy
+**/
+pub fn perplexity(names: &[String], bigram_matrix: &[Vec<f64>], mode: BoundaryMode) -> Option<f64> {
+    cross_entropy(names, bigram_matrix, mode).map(f64::exp)
+}
+
+/**
+    * A bootstrap estimate, the mean of the resampled values with a percentile interval
+**/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub mean: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/**
+    * Function to bootstrap the perplexity of a corpus
+    * Returns None without names or iterations
+    * 1. Score every name once
+    * 2. Resample the names with replacement `iterations` times and compute the perplexity of each resample
+    * 3. Report the mean and the percentiles of `confidence` around the middle, 0.95 for a 95% interval
+**/
+pub fn bootstrap_perplexity(names: &[String], bigram_matrix: &[Vec<f64>], mode: BoundaryMode, iterations: usize, confidence: f64, rng: &mut impl Rng) -> Option<Interval> {
+    if names.is_empty() || iterations == 0 {
+        return None;
+    }
+    let scores: Vec<(f64, usize)> = names.iter()
+        .map(|name| {
+            let score = score_word_with(name, bigram_matrix, mode);
+            (-score.log_probability, score.bigrams)
+        })
+        .collect();
+    let mut perplexities: Vec<f64> = (0..iterations).map(|_| {
+        let (nll, bigrams) = (0..scores.len())
+            .map(|_| scores[rng.random_range(0..scores.len())])
+            .fold((0.0, 0), |(nll, bigrams), (name_nll, name_bigrams)| (nll + name_nll, bigrams + name_bigrams));
+        if bigrams == 0 { 1.0 } else { (nll / bigrams as f64).exp() }
+    }).collect();
+    perplexities.sort_by(|a, b| a.total_cmp(b));
+
+    let tail = (1.0 - confidence.clamp(0.0, 1.0)) / 2.0;
+    let percentile = |q: f64| perplexities[((perplexities.len() - 1) as f64 * q).round() as usize];
+    Some(Interval {
+        mean: perplexities.iter().sum::<f64>() / iterations as f64,
+        lower: percentile(tail),
+        upper: percentile(1.0 - tail),
+    })
+}
+
+/**
+    * Function to shuffle the names and split them into a training and a test set
+    * test_fraction of the names, rounded down, end up in the test set
+**/
+pub fn train_test_split(mut names: Vec<String>, test_fraction: f64, rng: &mut impl Rng) -> (Vec<String>, Vec<String>) {
+    names.shuffle(rng);
+    let test_len = (names.len() as f64 * test_fraction.clamp(0.0, 1.0)) as usize;
+    let test = names.split_off(names.len() - test_len);
+    (names, test)
+}
+
+/**
+    * A point of a learning curve, the held-out perplexity of a model trained on the first names of the training set
+**/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    pub names: usize,
+    pub perplexity: f64,
+}
+
+/**
+    * Function to compute the held-out perplexity of models trained on growing shares of the training names
+    * Step k of the steps trains on the first k / steps of the names, rounded up, so every step holds the names
+    * of the ones before it and the last trains on all of them. Shuffle the names first for a fair sample.
+    * A curve still falling at the last step suggests more names would help, and a test set with nothing to score gives no points
+**/
+pub fn learning_curve(training: &[String], test: &[String], steps: usize, mode: BoundaryMode, train: impl Fn(&[String]) -> BigramModel) -> Vec<CurvePoint> {
+    (1..=steps)
+        .map(|step| (training.len() * step).div_ceil(steps))
+        .filter(|names| *names > 0)
+        .filter_map(|names| perplexity(test, &train(&training[..names]).matrix, mode).map(|perplexity| CurvePoint { names, perplexity }))
+        .collect()
+}
+
+/**
+    * Function to count how many generated names are real names of a reference list
+    * The reference holds cleaned names without their boundary dots, the way names are generated.
+    * Comparing the share of real names across sampling options gives a rough score of how realistic they are
+**/
+pub fn real_names(generated: &[String], reference: &HashSet<String>) -> usize {
+    generated.iter().filter(|name| reference.contains(name.as_str())).count()
+}
+
+/**
+    * Function to compute the two sample Kolmogorov-Smirnov statistic between the length distributions of two sets of names
+    * The largest difference between the two cumulative distributions of name lengths, boundary dots aside, so 0 means
+    * the same distribution and values near 1 a model generating names of systematically wrong length.
+    * Returns None if either set is empty
+**/
+pub fn length_ks_statistic(generated: &[String], training: &[String]) -> Option<f64> {
+    if generated.is_empty() || training.is_empty() {
+        return None;
+    }
+    let lengths = |names: &[String]| {
+        let mut lengths: Vec<usize> = names.iter().map(|name| name.trim_matches('.').chars().count()).collect();
+        lengths.sort_unstable();
+        lengths
+    };
+    let (a, b) = (lengths(generated), lengths(training));
+    let longest = a[a.len() - 1].max(b[b.len() - 1]);
+    // the cumulative share of names up to each length, lengths are discrete so every step is one length
+    let share = |lengths: &[usize], length: usize| lengths.partition_point(|l| *l <= length) as f64 / lengths.len() as f64;
+    Some((0..=longest).map(|length| (share(&a, length) - share(&b, length)).abs()).fold(0.0, f64::max))
+}
+
+/**
+    * Function to get the KS statistic two samples of this size exceed by chance 5% of the time
+    * This is the asymptotic value for continuous data, discrete lengths make it conservative
+**/
+pub fn ks_critical_value(n: usize, m: usize) -> f64 {
+    1.358 * ((n + m) as f64 / (n * m) as f64).sqrt()
+}
@@ -0,0 +1,102 @@
+/*!
+    * Export of the probability matrix as a PNG heatmap, using plotters
+ **/
+
+use std::error::Error;
+use std::path::Path;
+use plotters::prelude::*;
+use crate::{Alphabet, BigramModel};
+
+const WIDTH: u32 = 900;
+const HEIGHT: u32 = 800;
+const LEGEND_WIDTH: u32 = 120;
+
+/**
+    * Function to write the probability matrix of a model as a colored heatmap
+    * 1. Rows are the previous character and columns the next character, both labeled through the alphabet
+    * 2. Each cell is colored by its probability on the viridis gradient, scaled by the largest probability
+    * 3. A color scale legend is drawn to the right of the matrix
+**/
+pub fn export_png(model: &BigramModel, path: &Path) -> Result<(), Box<dyn Error>> {
+    let max = model.matrix.iter().flatten().cloned().fold(0.0, f64::max);
+    // keep the scale valid for an empty matrix
+    let max = if max > 0.0 { max } else { 1.0 };
+
+    let root = BitMapBackend::new(path, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let (matrix_area, legend_area) = root.split_horizontally(WIDTH - LEGEND_WIDTH);
+
+    let alphabet = &model.alphabet;
+    let size = model.size() as i32;
+    let mut chart = ChartBuilder::on(&matrix_area)
+        .caption("Bigram probabilities", ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(45)
+        .y_label_area_size(45)
+        .build_cartesian_2d((0..size).into_segmented(), (0..size).into_segmented())?;
+    chart.configure_mesh()
+        .disable_mesh()
+        .x_labels(model.size())
+        .y_labels(model.size())
+        .label_style(("sans-serif", 14))
+        .x_desc("next")
+        .y_desc("previous")
+        .x_label_formatter(&|value| segment_label(value, alphabet, model.size(), false))
+        .y_label_formatter(&|value| segment_label(value, alphabet, model.size(), true))
+        .draw()?;
+    chart.draw_series(model.matrix.iter().enumerate().flat_map(|(i, row)| {
+        row.iter().enumerate().map(move |(j, p)| {
+            // draw the first row at the top
+            let y = size - 1 - i as i32;
+            Rectangle::new(
+                [(SegmentValue::Exact(j as i32), SegmentValue::Exact(y + 1)), (SegmentValue::Exact(j as i32 + 1), SegmentValue::Exact(y))],
+                probability_color(*p, max).filled(),
+            )
+        })
+    }))?;
+
+    // the legend is a vertical strip of the gradient with probabilities on the axis
+    let steps = 100;
+    let mut legend = ChartBuilder::on(&legend_area)
+        .margin_top(44)
+        .margin_bottom(40)
+        .margin_right(10)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..1.0, 0.0..max)?;
+    legend.configure_mesh()
+        .disable_mesh()
+        .disable_x_axis()
+        .y_labels(6)
+        .label_style(("sans-serif", 14))
+        .y_label_formatter(&|p| format!("{:.3}", p))
+        .draw()?;
+    legend.draw_series((0..steps).map(|step| {
+        let low = max * step as f64 / steps as f64;
+        let high = max * (step + 1) as f64 / steps as f64;
+        Rectangle::new([(0.0, low), (1.0, high)], probability_color(low, max).filled())
+    }))?;
+
+    root.present()?;
+    Ok(())
+}
+
+/**
+    * Function to label an axis segment with the character of its row or column
+    * The y axis is flipped so the first row is drawn at the top
+**/
+fn segment_label(value: &SegmentValue<i32>, alphabet: &Alphabet, size: usize, flipped: bool) -> String {
+    match value {
+        SegmentValue::CenterOf(index) if *index >= 0 && (*index as usize) < size => {
+            let index = if flipped { size - 1 - *index as usize } else { *index as usize };
+            alphabet.char_at(index).to_string()
+        }
+        _ => String::new(),
+    }
+}
+
+/**
+    * Function to map a probability onto the viridis gradient
+**/
+fn probability_color(p: f64, max: f64) -> RGBColor {
+    ViridisRGB.get_color((p / max) as f32)
+}
@@ -0,0 +1,1265 @@
+/*!
+    * A simple rust library implementing a bigram language model
+ **/
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::ops::ControlFlow;
+use std::path::Path;
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub mod alphabet;
+pub mod attribution;
+pub mod chain;
+mod error;
+pub mod eval;
+#[cfg(feature = "png")]
+pub mod heatmap;
+pub mod mixture;
+mod persist;
+pub mod predicate;
+pub mod protocol;
+mod search;
+pub mod similarity;
+
+pub use alphabet::Alphabet;
+pub use error::BigramError;
+
+// number of characters in the model, the dot boundary plus a-z
+pub const ALPHABET_SIZE: usize = 27;
+// how many names are sampled while looking for one that passes a filter
+pub const MAX_ATTEMPTS: usize = 1000;
+// longest name sampling builds, far past any real name, so a chain that can't reach the end still stops
+pub const MAX_NAME_LEN: usize = 100;
+
+// a transition from one character to the next with a value, like its probability
+pub type Transition = (char, char, f64);
+
+/**
+    * A trained bigram model
+    * Keeps the raw bigram counts next to the normalized probability matrix
+    * so the model can be inspected or renormalized later
+**/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BigramModel {
+    // raw bigram counts, before smoothing
+    pub counts: Vec<Vec<f64>>,
+    // row-normalized transition probabilities
+    pub matrix: Vec<Vec<f64>>,
+    pub smoothing: f64,
+    // the boundary tokens, models saved before there was a choice share the dot
+    #[serde(default)]
+    pub alphabet: Alphabet,
+    // how much the start and end counts are scaled when the matrix is built, models saved before it are unweighted
+    #[serde(default = "unit_weight")]
+    pub boundary_weight: f64,
+}
+
+/**
+    * Function to give the boundary weight of models saved without one
+**/
+fn unit_weight() -> f64 {
+    1.0
+}
+
+/**
+    * Which transitions of a word are scored
+    * Inclusive scores every bigram of ".name.", including the start -> first character and
+    * last character -> end transitions, so a name of n letters has n + 1 bigrams.
+    * Interior skips those two boundary transitions and only scores the n - 1 bigrams inside the name,
+    * which compares how plausible the spelling is independent of how names start and end.
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoundaryMode {
+    #[default]
+    Inclusive,
+    Interior,
+}
+
+/**
+    * How much each occurrence of a training name counts towards the bigram counts
+    * Frequency counts every occurrence once, so common names dominate.
+    * InverseFrequency counts each occurrence 1/freq, so every distinct name adds up to the same weight.
+    * InverseSqrtFrequency counts each occurrence 1/sqrt(freq), a middle ground between the two.
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameWeighting {
+    #[default]
+    Frequency,
+    InverseFrequency,
+    InverseSqrtFrequency,
+}
+
+/**
+    * How the probabilities of a model are estimated from its counts
+    * Additive only adds the smoothing value to every count, which is what normalize_counts does.
+    * Interpolated mixes the observed bigram distribution of every row with the unigram one by a fixed lambda.
+    * ConfidenceWeighted gives each row a lambda of n / (n + k) from the n bigrams observed in its context,
+    * so well attested rows trust their bigrams and sparse rows lean on the unigram distribution.
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SmoothingMethod {
+    #[default]
+    Additive,
+    Interpolated(f64),
+    ConfidenceWeighted(f64),
+}
+
+/**
+    * Options that change how names are cleaned
+**/
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    // compose accents into single code points first, so decomposed and composed input clean the same
+    #[cfg(feature = "unicode")]
+    pub normalize_unicode: bool,
+    // keep upper case letters, for a case sensitive alphabet
+    pub case_sensitive: bool,
+    // keep a single space between words, for an alphabet with spaces
+    pub keep_spaces: bool,
+    // letters to strip from every name, matched after lower casing unless case sensitive
+    pub exclude: Vec<char>,
+}
+
+/**
+    * Options that change how names are sampled from the matrix
+    * Every step applies them to its row in a fixed order, so they compose instead of excluding each other
+    * 1. Drop the steps at or below the probability floor
+    * 2. Keep the first step off the boundary and multiply in the start bias
+    * 3. Rescale the row with the temperature
+    * 4. Cut the rescaled row down to its nucleus of top-p
+    * 5. Sample what is left, renormalized
+**/
+#[derive(Debug, Clone, Default)]
+pub struct SampleOptions {
+    // never let the first step sample the boundary, so a name is never empty
+    pub sanitize: bool,
+    // weights multiplied into the probability of each first character, a soft bias rather than a fixed prefix
+    pub start_bias: Vec<(char, f64)>,
+    // letters every name starts with, the chain carries on from the last of them
+    pub prefix: String,
+    // only steps more likely than this are sampled, unless none of the row is
+    pub min_step_prob: f64,
+    // each weight is raised to 1 / temperature, below 1 sharpens the row and above 1 flattens it
+    pub temperature: Option<f64>,
+    // only the most likely steps that together hold at least this share of the row are sampled
+    pub top_p: Option<f64>,
+    // never repeat a name within a batch, single names ignore it
+    pub unique: bool,
+}
+
+impl BigramModel {
+    /**
+        * Function to create a model directly from a probability matrix
+        * There are no counts behind such a matrix, so they are left as zeros
+        * The alphabet follows from the size of the matrix
+    **/
+    pub fn from_matrix(matrix: Vec<Vec<f64>>) -> BigramModel {
+        let counts = vec![vec![0.0; matrix.len()]; matrix.len()];
+        let alphabet = Alphabet::for_size(matrix.len());
+        BigramModel { counts, matrix, smoothing: 0.0, alphabet, boundary_weight: 1.0 }
+    }
+
+    /**
+        * Function to create a model from a matrix of raw bigram counts
+        * The counts are smoothed and normalized with normalize_counts
+        * The alphabet follows from the size of the matrix
+    **/
+    pub fn from_counts(counts: Vec<Vec<f64>>, smoothing: f64) -> BigramModel {
+        let matrix = normalize_counts(&counts, smoothing);
+        let alphabet = Alphabet::for_size(counts.len());
+        BigramModel { counts, matrix, smoothing, alphabet, boundary_weight: 1.0 }
+    }
+
+    /**
+        * Function to train a model on names held in memory, cleaned like clean_names and counted with count_matrix
+        * The seam for tests and for callers that already have their names, without a corpus file.
+        * Names with no letters, or with letters outside of a-z like accents, are dropped like filter_to_alphabet does.
+        * Fails like from_names when no name is left
+    **/
+    pub fn train_from_names(names: &[&str], smoothing: f64) -> Result<BigramModel, BigramError> {
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        let (names, _) = filter_to_alphabet(clean_names(&names));
+        BigramModel::from_names(&names, NameWeighting::Frequency, Alphabet::default(), smoothing)
+    }
+
+    /**
+        * Function to train a model on cleaned names, counted with the weighting into the alphabet
+        * Fails when there are no names, smoothing alone would give a uniform model that generates noise
+    **/
+    pub fn from_names(cleaned_names: &[String], weighting: NameWeighting, alphabet: Alphabet, smoothing: f64) -> Result<BigramModel, BigramError> {
+        BigramModel::from_names_with(cleaned_names, weighting, alphabet, smoothing, |_| {})
+    }
+
+    /**
+        * Function to train a model like from_names, reporting how many names were counted like count_matrix_weighted_with
+    **/
+    pub fn from_names_with(cleaned_names: &[String], weighting: NameWeighting, alphabet: Alphabet, smoothing: f64, counted: impl FnMut(usize)) -> Result<BigramModel, BigramError> {
+        if cleaned_names.is_empty() {
+            return Err(BigramError::EmptyCorpus);
+        }
+        let counts = count_matrix_weighted_with(cleaned_names, weighting, &alphabet, counted);
+        let matrix = normalize_counts_in(&counts, smoothing, &alphabet);
+        Ok(BigramModel { counts, matrix, smoothing, alphabet, boundary_weight: 1.0 })
+    }
+
+    /**
+        * Function to get the number of rows and columns of the matrix
+    **/
+    pub fn size(&self) -> usize {
+        self.matrix.len()
+    }
+
+    /**
+        * Function to create a model from string bigram counts, like the ones returned by count_bigrams
+        * 1. Parse each two character key through the alphabet
+        * 2. Fill the count matrix
+        * 3. Normalize the counts into probabilities
+        * Keys that are not exactly two characters of the alphabet are skipped,
+        * the alphabet is case sensitive if any key has an upper case letter
+    **/
+    pub fn from_string_counts(counts: &HashMap<String, i32>, smoothing: f64) -> BigramModel {
+        let case_sensitive = counts.keys().any(|bigram| bigram.chars().any(|c| c.is_ascii_uppercase()));
+        let alphabet = Alphabet::default().with_case(case_sensitive);
+        let mut count_matrix = vec![vec![0.0; alphabet.size()]; alphabet.size()];
+        for (bigram, count) in counts {
+            let chars = bigram.chars().collect::<Vec<char>>();
+            if chars.len() != 2 {
+                continue;
+            }
+            if let (Some(first), Some(second)) = (alphabet.index_of(chars[0]), alphabet.index_of(chars[1])) {
+                count_matrix[first][second] += *count as f64;
+            }
+        }
+        BigramModel::from_counts(count_matrix, smoothing)
+    }
+
+    /**
+        * Function to add more raw counts to the model, for example from a new batch of names
+        * The matrix is not updated until renormalize is called
+    **/
+    pub fn add_counts(&mut self, counts: &[Vec<f64>]) {
+        for (row, new_row) in self.counts.iter_mut().zip(counts) {
+            for (count, new_count) in row.iter_mut().zip(new_row) {
+                *count += new_count;
+            }
+        }
+    }
+
+    /**
+        * Function to scale the counts out of the start and into the end before normalizing, see weight_boundary_counts
+        * The counts stay raw, the weight is kept on the model so renormalizing and saving keep it too.
+        * The matrix is recomputed with additive smoothing, so other smoothing methods have to go after this
+    **/
+    pub fn apply_boundary_weight(&mut self, weight: f64) {
+        self.boundary_weight = weight;
+        self.renormalize();
+    }
+
+    /**
+        * Function to get the counts the matrix is built from, the raw counts scaled by the boundary weight
+    **/
+    pub fn weighted_counts(&self) -> Cow<'_, [Vec<f64>]> {
+        if self.boundary_weight == 1.0 {
+            return Cow::Borrowed(&self.counts);
+        }
+        let mut counts = self.counts.clone();
+        weight_boundary_counts(&mut counts, self.boundary_weight, &self.alphabet);
+        Cow::Owned(counts)
+    }
+
+    /**
+        * Function to recompute the probability matrix from the raw counts and the boundary weight
+    **/
+    pub fn renormalize(&mut self) {
+        self.matrix = normalize_counts_in(&self.weighted_counts(), self.smoothing, &self.alphabet);
+    }
+
+    /**
+        * Function to compute the unigram continuation distribution of the corpus
+        * This is how often each character appears as the second character of a bigram,
+        * smoothed the same way as the rows of the matrix and counted with the boundary weight like them
+    **/
+    pub fn unigram_distribution(&self) -> Vec<f64> {
+        let (_, columns) = count_marginals(&self.weighted_counts());
+        let mut unigram: Vec<f64> = columns.iter().map(|count| count + self.smoothing).collect();
+        let total: f64 = unigram.iter().sum();
+        if total > 0.0 {
+            unigram.iter_mut().for_each(|p| *p /= total);
+        }
+        unigram
+    }
+
+    /**
+        * Function to compute the pointwise mutual information of every bigram seen in training
+        * PMI is ln(P(a, b) / (P(a) P(b))) with the joint from the raw counts and the marginals from
+        * their row and column totals, so pairs that go together score high even when they are rare.
+        * Unseen pairs would all be -infinity and are left out. Returns the pairs from highest to lowest
+    **/
+    pub fn pointwise_mutual_information(&self) -> Vec<Transition> {
+        let (rows, columns) = count_marginals(&self.counts);
+        let total: f64 = rows.iter().sum();
+        let mut pairs: Vec<Transition> = Vec::new();
+        for (i, row) in self.counts.iter().enumerate() {
+            for (j, count) in row.iter().enumerate() {
+                if *count > 0.0 {
+                    let pmi = (count * total / (rows[i] * columns[j])).ln();
+                    pairs.push((self.alphabet.char_at(i), self.alphabet.char_at(j), pmi));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+        pairs
+    }
+
+    /**
+        * Function to get the k bigrams with the highest and the k with the lowest pointwise mutual information
+        * The lowest are listed from the lowest up
+    **/
+    pub fn pmi_extremes(&self, k: usize) -> (Vec<Transition>, Vec<Transition>) {
+        let pairs = self.pointwise_mutual_information();
+        let highest = pairs.iter().take(k).cloned().collect();
+        let lowest = pairs.iter().rev().take(k).cloned().collect();
+        (highest, lowest)
+    }
+
+    /**
+        * Function to sum the probability of moving into each character over every row of the matrix
+        * A character with a tiny inbound mass is almost never generated, whatever came before it
+    **/
+    pub fn inbound_mass(&self) -> Vec<f64> {
+        let mut inbound = vec![0.0; self.size()];
+        for row in &self.matrix {
+            for (j, p) in row.iter().enumerate() {
+                inbound[j] += p;
+            }
+        }
+        inbound
+    }
+
+    /**
+        * Function to compute the entropy of the next character after each character, in bits
+        * A low entropy row almost always continues the same way, a row of all zeros has an entropy of 0
+    **/
+    pub fn row_entropies(&self) -> Vec<f64> {
+        self.matrix.iter()
+            .map(|row| -row.iter().filter(|p| **p > 0.0).map(|p| p * p.log2()).sum::<f64>())
+            .collect()
+    }
+
+    /**
+        * Function to get the k bigrams counted most often in training, with their counts
+    **/
+    pub fn most_common_bigrams(&self, k: usize) -> Vec<Transition> {
+        let mut bigrams: Vec<Transition> = self.counts.iter().enumerate()
+            .flat_map(|(i, row)| row.iter().enumerate().map(move |(j, count)| (i, j, *count)))
+            .filter(|(_, _, count)| *count > 0.0)
+            .map(|(i, j, count)| (self.alphabet.char_at(i), self.alphabet.char_at(j), count))
+            .collect();
+        bigrams.sort_by(|a, b| b.2.total_cmp(&a.2));
+        bigrams.truncate(k);
+        bigrams
+    }
+
+    /**
+        * Function to list the letters whose inbound probability mass is below a threshold
+        * These are only reachable through smoothing, like 'q' in a corpus without it
+    **/
+    pub fn dead_characters(&self, threshold: f64) -> Vec<(char, f64)> {
+        let inbound = self.inbound_mass();
+        self.alphabet.letters()
+            .filter(|i| !self.alphabet.is_excluded(*i))
+            .map(|i| (i, inbound[i]))
+            .filter(|(_, mass)| *mass < threshold)
+            .map(|(i, mass)| (self.alphabet.char_at(i), mass))
+            .collect()
+    }
+
+    /**
+        * Function to list the letters no training name started with
+    **/
+    pub fn never_starting(&self) -> Vec<char> {
+        self.alphabet.letters()
+            .filter(|j| !self.alphabet.is_excluded(*j) && self.counts[0][*j] == 0.0)
+            .map(|j| self.alphabet.char_at(j))
+            .collect()
+    }
+
+    /**
+        * Function to back off to the unigram distribution for contexts never seen in training
+        * Rows whose observed count is zero would otherwise be all smoothing (uniform),
+        * so they are replaced with the unigram continuation distribution
+    **/
+    pub fn apply_unigram_backoff(&mut self) {
+        let unigram = self.unigram_distribution();
+        for (i, row) in self.counts.iter().enumerate() {
+            if row.iter().sum::<f64>() > 0.0 {
+                continue;
+            }
+            // the backoff still can't make transitions the alphabet forbids, like an empty name
+            let backoff: Vec<f64> = unigram.iter().enumerate()
+                .map(|(j, p)| if self.alphabet.is_allowed(i, j) { *p } else { 0.0 })
+                .collect();
+            let total: f64 = backoff.iter().sum();
+            if backoff == unigram {
+                self.matrix[i] = backoff;
+            } else if total > 0.0 {
+                // nothing follows the end of a name, so that row is left alone
+                self.matrix[i] = backoff.iter().map(|p| p / total).collect();
+            }
+        }
+    }
+
+    /**
+        * Function to re-estimate the matrix from the counts with a smoothing method
+        * Additive leaves the matrix alone, the others recompute every row so applying them twice changes nothing
+    **/
+    pub fn apply_smoothing_method(&mut self, method: SmoothingMethod) {
+        match method {
+            SmoothingMethod::Additive => {}
+            SmoothingMethod::Interpolated(lambda) => self.interpolate_with_unigram(|_| lambda),
+            SmoothingMethod::ConfidenceWeighted(k) => self.interpolate_with_unigram(|n| n / (n + k)),
+        }
+    }
+
+    /**
+        * Function to mix the observed distribution of each row with the unigram distribution
+        * 1. Work out the lambda of each row from how many bigrams were observed in it, a row with none gets 0
+        * 2. Mix lambda of the observed row with 1 - lambda of the unigram distribution
+        * 3. Reset the cells the alphabet forbids and renormalize the row
+    **/
+    fn interpolate_with_unigram(&mut self, lambda: impl Fn(f64) -> f64) {
+        let unigram = self.unigram_distribution();
+        let counts = self.weighted_counts().into_owned();
+        let (rows, _) = count_marginals(&counts);
+        for (i, observed) in rows.iter().enumerate() {
+            let weight = if *observed > 0.0 { lambda(*observed) } else { 0.0 };
+            let mut row: Vec<f64> = (0..self.size())
+                .map(|j| {
+                    if !self.alphabet.is_allowed(i, j) {
+                        return 0.0;
+                    }
+                    let bigram = if *observed > 0.0 { counts[i][j] / observed } else { 0.0 };
+                    weight * bigram + (1.0 - weight) * unigram[j]
+                })
+                .collect();
+            let total: f64 = row.iter().sum();
+            if total > 0.0 {
+                row.iter_mut().for_each(|p| *p /= total);
+            }
+            self.matrix[i] = row;
+        }
+    }
+
+    /**
+        * Function to pin transitions by multiplying their probability and renormalizing their rows
+        * Each constraint is (previous, next, multiplier), a multiplier of 0 forbids the transition.
+        * This works on the probability matrix, after smoothing, so a forbidden cell is truly 0.
+        * renormalize recomputes the matrix from the counts, so constraints have to be applied again after it.
+        * Fails without changing the model if a character is outside of the alphabet
+    **/
+    pub fn apply_constraints(&mut self, constraints: &[(char, char, f64)]) -> Result<(), BigramError> {
+        let mut cells = Vec::with_capacity(constraints.len());
+        for (previous, next, multiplier) in constraints {
+            let row = self.alphabet.index_of(*previous).ok_or(BigramError::InvalidCharacter(*previous))?;
+            let column = self.alphabet.index_of(*next).ok_or(BigramError::InvalidCharacter(*next))?;
+            cells.push((row, column, *multiplier));
+        }
+        for (row, column, multiplier) in &cells {
+            self.matrix[*row][*column] *= multiplier;
+        }
+        for (row, _, _) in &cells {
+            let total: f64 = self.matrix[*row].iter().sum();
+            // a row with every transition forbidden is left as zeros
+            if total > 0.0 {
+                self.matrix[*row].iter_mut().for_each(|p| *p /= total);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+        * Function to sample a single name from the model
+        * 1. Start from the start boundary
+        * 2. Sample the next character from the row of the current character
+        * 3. Stop once the end boundary is sampled
+        * The returned name does not include the boundaries
+    **/
+    pub fn sample_name(&self, rng: &mut impl Rng) -> String {
+        self.sample_name_with(rng, &SampleOptions::default())
+    }
+
+    /**
+        * Function to sample a single name, adjusting each step with the sampling options
+        * The boundary is only ever sampled to end the name
+        * A prefix is taken as given, a character of it outside of the alphabet restarts the chain from the start
+        * When the options leave a step no weight, like a start bias of 0 on every first letter, the step is taken
+        * from the row as trained. A row that is all zeros, like one every transition of was forbidden, ends the name,
+        * and so does reaching MAX_NAME_LEN characters
+    **/
+    pub fn sample_name_with(&self, rng: &mut impl Rng, options: &SampleOptions) -> String {
+        let mut name = options.prefix.clone();
+        let mut current_char = name.chars().last().and_then(|c| self.alphabet.index_of(c)).unwrap_or(0);
+        let end = self.alphabet.end_index();
+        while name.chars().count() < MAX_NAME_LEN {
+            let row = &self.matrix[current_char];
+            let weights = step_weights(row, name.is_empty(), &self.alphabet, options);
+            current_char = sample_next_char(&weights, rng).or_else(|| sample_next_char(row, rng)).unwrap_or(end);
+            if current_char == end {
+                break;
+            }
+            name.push(self.alphabet.char_at(current_char));
+        }
+        name
+    }
+
+    /**
+        * Function to get the distribution of the next character given the text so far
+        * 1. Map the last character of the context to its row, an empty context is the start of a name
+        * 2. Label each probability in that row with its character
+        * 3. Sort from most to least likely
+    **/
+    pub fn next_char_distribution(&self, context: &str) -> Result<Vec<(char, f64)>, BigramError> {
+        let last = context.chars().last().unwrap_or(self.alphabet.start);
+        let row = self.alphabet.index_of(last).ok_or(BigramError::InvalidCharacter(last))?;
+        let mut distribution: Vec<(char, f64)> = self.matrix[row].iter()
+            .enumerate()
+            .map(|(i, p)| (self.alphabet.char_at(i), *p))
+            .collect();
+        distribution.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(distribution)
+    }
+
+    /**
+        * Function to list every scored transition of a word with its probability
+        * The word is cleaned like likelihood_of_word, boundaries are shown as the alphabet tokens
+        * and characters outside of the alphabet as themselves, with a probability of 0
+    **/
+    pub fn transition_breakdown(&self, word: &str, mode: BoundaryMode) -> Vec<Transition> {
+        let cleaned = clean_name_in(word, &self.alphabet);
+        let labels: Vec<char> = self.alphabet.name_indices(&cleaned).iter().zip(cleaned.chars())
+            .map(|(index, c)| index.map_or(c, |i| self.alphabet.char_at(i)))
+            .collect();
+        let transitions: Vec<Transition> = labels.windows(2)
+            .zip(transition_probabilities(word, &self.matrix))
+            .map(|(pair, p)| (pair[0], pair[1], p))
+            .collect();
+        match mode {
+            BoundaryMode::Inclusive => transitions,
+            BoundaryMode::Interior => transitions.iter().skip(1).take(transitions.len().saturating_sub(2)).cloned().collect(),
+        }
+    }
+
+    /**
+        * Function to list the characters of a word the alphabet has no row for, like excluded letters, once each
+        * The word is cleaned like likelihood_of_word, so these are the characters that score a probability of 0
+    **/
+    pub fn out_of_vocabulary(&self, word: &str) -> Vec<char> {
+        let cleaned = clean_name_in(word, &self.alphabet);
+        let mut unknown: Vec<char> = Vec::new();
+        for (index, c) in self.alphabet.name_indices(&cleaned).into_iter().zip(cleaned.chars()) {
+            if index.is_none() && !unknown.contains(&c) {
+                unknown.push(c);
+            }
+        }
+        unknown
+    }
+
+    /**
+        * Function to get how fast the score of a word moves with the additive smoothing value k
+        * Under add-k smoothing a transition has p = (c + k) / (N + kV), with c its count, N the total of its row
+        * and V the transitions the row allows, so d ln p / dk = 1 / (c + k) - V / (N + kV).
+        * A well attested transition barely moves, while one only smoothing makes possible moves by about 1/k,
+        * so a large negative value means the score leans on the smoothing floor rather than on the corpus.
+        * A context never seen in training is uniform whatever k is, so its transitions don't move at all.
+        * This differentiates additive smoothing at the counts of the model, whichever method built its matrix.
+        * Returns the derivative of the negative log likelihood per scored bigram, and the derivative of -ln p
+        * of every transition like transition_breakdown, 0 for characters outside of the alphabet
+    **/
+    pub fn smoothing_sensitivity(&self, word: &str, mode: BoundaryMode) -> (f64, Vec<Transition>) {
+        let k = self.smoothing;
+        let cleaned = clean_name_in(word, &self.alphabet);
+        let indices = self.alphabet.name_indices(&cleaned);
+        let labels: Vec<char> = indices.iter().zip(cleaned.chars())
+            .map(|(index, c)| index.map_or(c, |i| self.alphabet.char_at(i)))
+            .collect();
+        let transitions: Vec<Transition> = indices.windows(2).zip(labels.windows(2))
+            .map(|(pair, label)| {
+                let derivative = match (pair[0], pair[1]) {
+                    (Some(i), Some(j)) if self.alphabet.is_allowed(i, j) => {
+                        let row = &self.counts[i];
+                        let allowed = (0..row.len()).filter(|n| self.alphabet.is_allowed(i, *n)).count() as f64;
+                        let total: f64 = row.iter().sum();
+                        -(1.0 / (row[j] + k) - allowed / (total + k * allowed))
+                    }
+                    _ => 0.0,
+                };
+                (label[0], label[1], derivative)
+            })
+            .collect();
+        let transitions: Vec<Transition> = match mode {
+            BoundaryMode::Inclusive => transitions,
+            BoundaryMode::Interior => transitions.iter().skip(1).take(transitions.len().saturating_sub(2)).cloned().collect(),
+        };
+        let mean = transitions.iter().map(|(_, _, derivative)| derivative).sum::<f64>() / transitions.len().max(1) as f64;
+        (mean, transitions)
+    }
+
+    /**
+        * Function to sample names until one is accepted by the filter
+        * Returns None if nothing was accepted after MAX_ATTEMPTS names
+    **/
+    pub fn sample_matching(&self, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool) -> Option<String> {
+        (0..MAX_ATTEMPTS)
+            .map(|_| self.sample_name_with(rng, options))
+            .find(|name| accept(name))
+    }
+
+    /**
+        * Function to sample a name that starts with the prefix of the options and ends with a suffix
+        * The prefix is fixed and the rest of the name sampled after it, names that don't end in the suffix are
+        * rejected, so an accepted name follows the model conditioned on both.
+        * If a suffix is too unlikely after the prefix for MAX_ATTEMPTS samples to reach it, this falls back to
+        * the single most likely name with both of at most max_len letters, so repeated calls give the same name.
+        * Returns None if even that doesn't exist, and fails if either holds a character outside of the alphabet
+    **/
+    pub fn sample_with_affixes(&self, rng: &mut impl Rng, options: &SampleOptions, suffix: &str, max_len: usize) -> Result<Option<String>, BigramError> {
+        self.affix_indices(&options.prefix)?;
+        self.affix_indices(suffix)?;
+        if let Some(name) = self.sample_matching(rng, options, |name| name.ends_with(suffix)) {
+            return Ok(Some(name));
+        }
+        Ok(self.most_likely_with_affixes(&options.prefix, suffix, max_len)?.map(|(name, _)| name))
+    }
+
+    /**
+        * Function to sample a batch of names with no name repeated within the batch
+        * A repeat is resampled, up to MAX_ATTEMPTS times for each name, so a small model
+        * that can only make a few distinct names returns fewer than count
+    **/
+    pub fn generate_unique(&self, count: usize, rng: &mut impl Rng) -> Vec<String> {
+        self.generate_unique_with(count, rng, &SampleOptions::default(), |_| true)
+    }
+
+    /**
+        * Function to sample a batch of distinct names like generate_unique, each passing the filter
+        * Repeats count as rejected names, so the batch is cut short the same way as generate_scored
+    **/
+    pub fn generate_unique_with(&self, count: usize, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool) -> Vec<String> {
+        let options = SampleOptions { unique: true, ..options.clone() };
+        let mut names = Vec::with_capacity(count);
+        self.draw_names(count, rng, &options, accept, |_, name| {
+            names.push(name);
+            ControlFlow::Continue(())
+        });
+        names
+    }
+}
+
+/**
+    * Something batches of names are drawn from, a single model or a set of models that picks one per name
+**/
+pub trait NameSource {
+    /**
+        * Function to sample a name that passes the filter like BigramModel::sample_matching
+        * Returns the index of the model that made it with the name
+    **/
+    fn sample_source(&self, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool) -> Option<(usize, String)>;
+
+    /**
+        * Function to get the model of an index returned by sample_source
+    **/
+    fn source_model(&self, index: usize) -> &BigramModel;
+
+    /**
+        * Function to draw up to count names, each passing the filter and not drawn before when the options are unique
+        * Every name is handed to on_name with the index of its model as soon as it is drawn, so a caller can
+        * stream names or report progress. The batch is cut short when MAX_ATTEMPTS names in a row are rejected,
+        * or when on_name breaks. Unique options remember every name drawn, so keep count bounded with them.
+        * Returns how many names were drawn
+    **/
+    fn draw_names(&self, count: usize, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool, mut on_name: impl FnMut(usize, String) -> ControlFlow<()>) -> usize {
+        let mut seen: HashSet<String> = HashSet::new();
+        for drawn in 0..count {
+            let fresh = |name: &str| !(options.unique && seen.contains(name)) && accept(name);
+            let Some((index, name)) = self.sample_source(rng, options, fresh) else {
+                return drawn;
+            };
+            if options.unique {
+                seen.insert(name.clone());
+            }
+            if on_name(index, name).is_break() {
+                return drawn + 1;
+            }
+        }
+        count
+    }
+
+    /**
+        * Function to draw a batch of names like draw_names, each with its negative log likelihood per scored bigram
+        * under the model that made it. on_name sees every name as it is drawn
+    **/
+    fn generate_scored(&self, count: usize, rng: &mut impl Rng, options: &SampleOptions, mode: BoundaryMode, accept: impl Fn(&str) -> bool, mut on_name: impl FnMut(&str)) -> Vec<(String, f64)> {
+        let mut scored = Vec::with_capacity(count);
+        self.draw_names(count, rng, options, accept, |index, name| {
+            on_name(&name);
+            let nll = score_word_with(&name, &self.source_model(index).matrix, mode).nll_per_char;
+            scored.push((name, nll));
+            ControlFlow::Continue(())
+        });
+        scored
+    }
+}
+
+impl NameSource for BigramModel {
+    fn sample_source(&self, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool) -> Option<(usize, String)> {
+        self.sample_matching(rng, options, accept).map(|name| (0, name))
+    }
+
+    fn source_model(&self, _index: usize) -> &BigramModel {
+        self
+    }
+}
+
+/**
+    * Function to get the weights to sample the next character from, applying the options in the order of SampleOptions
+    * The row is only copied when an option actually changes it
+    * Steps at or below the probability floor are dropped and the survivors renormalized by the sampler,
+    * a row with nothing above the floor is sampled in full. The nucleus always keeps the most likely step
+**/
+fn step_weights<'a>(row: &'a [f64], first_step: bool, alphabet: &Alphabet, options: &SampleOptions) -> Cow<'a, [f64]> {
+    let mut weights = Cow::Borrowed(row);
+    let end = alphabet.end_index();
+    if options.min_step_prob > 0.0 && row.iter().any(|p| *p > options.min_step_prob) {
+        weights.to_mut().iter_mut().filter(|p| **p <= options.min_step_prob).for_each(|p| *p = 0.0);
+    }
+    if options.sanitize && first_step && weights[end] != 0.0 {
+        let weights = weights.to_mut();
+        weights[end] = 0.0;
+        // nothing but the boundary was possible, so any letter will do
+        if weights.iter().all(|w| *w <= 0.0) {
+            alphabet.letters().filter(|i| !alphabet.is_excluded(*i)).for_each(|i| weights[i] = 1.0);
+        }
+    }
+    if first_step && !options.start_bias.is_empty() {
+        // the sampler renormalizes the scaled row
+        let weights = weights.to_mut();
+        for (c, bias) in &options.start_bias {
+            if let Some(index) = alphabet.index_of(*c) {
+                weights[index] *= bias;
+            }
+        }
+    }
+    if let Some(temperature) = options.temperature {
+        let weights = weights.to_mut();
+        // scaled by the largest weight first, so a low temperature doesn't underflow the whole row to zero
+        let max = weights.iter().cloned().fold(0.0, f64::max);
+        if max > 0.0 {
+            weights.iter_mut().filter(|w| **w > 0.0).for_each(|w| *w = (*w / max).powf(1.0 / temperature));
+        }
+    }
+    if let Some(top_p) = options.top_p {
+        let weights = weights.to_mut();
+        let nucleus = top_p * weights.iter().sum::<f64>();
+        let mut order: Vec<usize> = (0..weights.len()).filter(|i| weights[*i] > 0.0).collect();
+        order.sort_by(|a, b| weights[*b].total_cmp(&weights[*a]));
+        let mut kept = 0.0;
+        for i in order {
+            if kept > 0.0 && kept >= nucleus {
+                weights[i] = 0.0;
+            } else {
+                kept += weights[i];
+            }
+        }
+    }
+    weights
+}
+
+/**
+    * Function to clean the names
+    * 1. Remove any non-alphabetic characters, keeping one space between words if the options ask for it
+    * 2. Convert to lowercase, unless the options are case sensitive
+    * 3. Add a dot to the start and end of the name
+**/
+pub fn clean_name(name: &str) -> String {
+    clean_name_with(name, &CleanOptions::default())
+}
+
+/**
+    * Function to clean a name like clean_name, with the cleaning options applied first
+**/
+pub fn clean_name_with(name: &str, options: &CleanOptions) -> String {
+    let name = normalize(name, options);
+    // remove any non-alphabetic or excluded characters
+    let keep = |c: &char| c.is_alphabetic() && !is_excluded_char(*c, options);
+    let name: String = if options.keep_spaces {
+        name.split_whitespace()
+            .map(|word| word.chars().filter(keep).collect::<String>())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<String>>()
+            .join(" ")
+    } else {
+        name.chars().filter(keep).collect()
+    };
+    // convert to lowercase, unless upper case letters have rows of their own
+    let name = if options.case_sensitive { name } else { name.to_lowercase() };
+    // add dot to start and end
+    format!(".{}.", name)
+}
+
+/**
+    * Function to check whether the options strip a character, an excluded "z" strips "Z" too unless case sensitive
+**/
+fn is_excluded_char(c: char, options: &CleanOptions) -> bool {
+    match options.case_sensitive {
+        true => options.exclude.contains(&c),
+        false => c.to_lowercase().any(|lower| options.exclude.contains(&lower)),
+    }
+}
+
+/**
+    * Function to clean a word for scoring, keeping its case and spaces when the alphabet has rows for them
+    * Excluded letters are kept, so scoring finds them outside of the alphabet
+**/
+// the update fills in the fields of the optional features
+#[allow(clippy::needless_update)]
+fn clean_name_in(word: &str, alphabet: &Alphabet) -> String {
+    clean_name_with(word, &CleanOptions { case_sensitive: alphabet.case_sensitive, keep_spaces: alphabet.spaces, ..CleanOptions::default() })
+}
+
+/**
+    * Function to compose decomposed accents when the options ask for it
+    * "e" followed by a combining acute becomes the single "é", which is_alphabetic keeps whole
+**/
+#[cfg(feature = "unicode")]
+fn normalize<'a>(name: &'a str, options: &CleanOptions) -> Cow<'a, str> {
+    use unicode_normalization::UnicodeNormalization;
+    if options.normalize_unicode {
+        Cow::Owned(name.nfc().collect())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+#[cfg(not(feature = "unicode"))]
+fn normalize<'a>(name: &'a str, _options: &CleanOptions) -> Cow<'a, str> {
+    Cow::Borrowed(name)
+}
+
+/**
+    * Function to read a corpus file with one name per line
+    * A file ending in .gz is decompressed, like read_corpus_with
+**/
+pub fn read_corpus(path: &Path) -> Result<Vec<String>, BigramError> {
+    read_corpus_with(path, false)
+}
+
+/**
+    * Function to read a corpus file, decompressing it when asked to or when it ends in .gz
+    * The file is decompressed as its lines are read, so the compressed bytes are never held whole,
+    * but every line is collected, so memory still grows with the decompressed corpus.
+    * Fails if the file isn't valid gzip, or if the gzip feature is off
+**/
+pub fn read_corpus_with(path: &Path, gzip: bool) -> Result<Vec<String>, BigramError> {
+    let file = File::open(path).map_err(|e| BigramError::Io(format!("{}: {}", path.display(), e)))?;
+    let gzip = gzip || path.extension().is_some_and(|extension| extension == "gz");
+    let reader: Box<dyn BufRead> = if gzip { gzip_reader(file, path)? } else { Box::new(BufReader::new(file)) };
+    reader.lines()
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| match gzip {
+            true => BigramError::Io(format!("{}: could not decompress: {}", path.display(), e)),
+            false => BigramError::Io(format!("{}: {}", path.display(), e)),
+        })
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_reader(file: File, _path: &Path) -> Result<Box<dyn BufRead>, BigramError> {
+    // several gzip members one after the other read as one file, like gunzip does
+    Ok(Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file))))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_reader(_file: File, path: &Path) -> Result<Box<dyn BufRead>, BigramError> {
+    Err(BigramError::Io(format!("{}: reading gzip needs the gzip feature, rebuild with `--features gzip`", path.display())))
+}
+
+/**
+    * Function to clean every name of a corpus, dropping the ones with no letters left
+    * An empty name would clean to "..", a bigram no real name has
+**/
+pub fn clean_names(names: &[String]) -> Vec<String> {
+    clean_names_with(names, &CleanOptions::default())
+}
+
+/**
+    * Function to clean every name of a corpus with the cleaning options, dropping empty names
+**/
+pub fn clean_names_with(names: &[String], options: &CleanOptions) -> Vec<String> {
+    names.iter()
+        .map(|name| clean_name_with(name, options))
+        .filter(|name| name != "..")
+        .collect()
+}
+
+/**
+    * Function to drop cleaned names with letters the matrix has no row for, like accented letters
+    * Returns the names that were kept and how many were dropped
+**/
+pub fn filter_to_alphabet(names: Vec<String>) -> (Vec<String>, usize) {
+    filter_to_alphabet_in(names, &Alphabet::default())
+}
+
+/**
+    * Function to drop cleaned names with letters a given alphabet has no row for
+    * A case sensitive alphabet keeps upper case letters, the default one drops them
+**/
+pub fn filter_to_alphabet_in(names: Vec<String>, alphabet: &Alphabet) -> (Vec<String>, usize) {
+    let total = names.len();
+    let kept: Vec<String> = names.into_iter()
+        .filter(|name| alphabet.name_indices(name).iter().all(Option::is_some))
+        .collect();
+    let dropped = total - kept.len();
+    (kept, dropped)
+}
+
+/**
+    * Function to drop cleaned names outside of a length range
+    * The length is measured without the boundary dots
+    * Returns the names that were kept and how many were dropped
+**/
+pub fn filter_by_length(names: Vec<String>, min_len: usize, max_len: usize) -> (Vec<String>, usize) {
+    let total = names.len();
+    let kept: Vec<String> = names.into_iter()
+        .filter(|name| (min_len..=max_len).contains(&name.chars().count().saturating_sub(2)))
+        .collect();
+    let dropped = total - kept.len();
+    (kept, dropped)
+}
+
+/**
+    * Function to count the bigrams
+    * 1. Create a hashmap to store the bigram counts
+    * 2. Iterate over the names
+    * 3. For each name, iterate over the characters
+    * 4. For each character, get the bigram and increment the count
+**/
+pub fn count_bigrams(names: &[String]) -> HashMap<String, i32> {
+    let mut bigram_counts = HashMap::new();
+    for name in names {
+        // windows of two characters, so names shorter than a bigram add nothing
+        let chars = name.chars().collect::<Vec<char>>();
+        for bigram in chars.windows(2) {
+            let count = bigram_counts.entry(bigram.iter().collect::<String>()).or_insert(0);
+            *count += 1;
+        }
+    }
+    bigram_counts
+}
+
+/**
+    * Function to count the bigrams into a fixed size matrix
+    * 1. Create a matrix of zeros
+    * 2. For each bigram in each name, find the row of the first character and the column of the second
+    * 3. Increment the count at that cell
+**/
+pub fn count_matrix(names: &[String]) -> Vec<Vec<f64>> {
+    count_matrix_in(names, &Alphabet::default())
+}
+
+/**
+    * Function to count the bigrams into a matrix laid out by an alphabet
+    * With split boundary tokens the trailing dot of each name is counted in the end column
+**/
+pub fn count_matrix_in(names: &[String], alphabet: &Alphabet) -> Vec<Vec<f64>> {
+    let mut counts = vec![vec![0.0; alphabet.size()]; alphabet.size()];
+    for name in names {
+        add_name_counts(&mut counts, name, alphabet, 1.0);
+    }
+    counts
+}
+
+/**
+    * Function to count the bigrams into a matrix, weighting each occurrence of a name by how common it is
+    * 1. Count how often each distinct name appears
+    * 2. Work out the weight of one occurrence from that frequency
+    * 3. Add the bigrams of each distinct name once, scaled by its frequency times that weight
+**/
+pub fn count_matrix_weighted(names: &[String], weighting: NameWeighting, alphabet: &Alphabet) -> Vec<Vec<f64>> {
+    count_matrix_weighted_with(names, weighting, alphabet, |_| {})
+}
+
+/**
+    * Function to count the bigrams like count_matrix_weighted, calling counted with how many names were just counted
+    * The calls add up to the number of names, so a caller can show the progress of a large corpus
+**/
+pub fn count_matrix_weighted_with(names: &[String], weighting: NameWeighting, alphabet: &Alphabet, mut counted: impl FnMut(usize)) -> Vec<Vec<f64>> {
+    let mut counts = vec![vec![0.0; alphabet.size()]; alphabet.size()];
+    if weighting == NameWeighting::Frequency {
+        for name in names {
+            add_name_counts(&mut counts, name, alphabet, 1.0);
+            counted(1);
+        }
+        return counts;
+    }
+    // distinct names in the order they are first seen, so the counts add up the same way on every run
+    let mut frequencies: Vec<(&str, usize)> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for name in names {
+        let position = *positions.entry(name).or_insert_with(|| {
+            frequencies.push((name, 0));
+            frequencies.len() - 1
+        });
+        frequencies[position].1 += 1;
+    }
+    for (name, frequency) in frequencies {
+        counted(frequency);
+        let frequency = frequency as f64;
+        let weight = match weighting {
+            NameWeighting::Frequency => 1.0,
+            NameWeighting::InverseFrequency => 1.0 / frequency,
+            NameWeighting::InverseSqrtFrequency => 1.0 / frequency.sqrt(),
+        };
+        add_name_counts(&mut counts, name, alphabet, frequency * weight);
+    }
+    counts
+}
+
+/**
+    * Function to add the bigrams of one cleaned name to a count matrix
+**/
+fn add_name_counts(counts: &mut [Vec<f64>], name: &str, alphabet: &Alphabet, weight: f64) {
+    let indices = alphabet.name_indices(name);
+    for bigram in indices.windows(2) {
+        let first = bigram[0].expect("names should be cleaned before counting");
+        let second = bigram[1].expect("names should be cleaned before counting");
+        counts[first][second] += weight;
+    }
+}
+
+/**
+    * Function to total the counts of each row and each column
+    * The rows count how often a character comes first in a bigram, the columns how often it comes second
+**/
+fn count_marginals(counts: &[Vec<f64>]) -> (Vec<f64>, Vec<f64>) {
+    let rows: Vec<f64> = counts.iter().map(|row| row.iter().sum()).collect();
+    let mut columns = vec![0.0; counts.len()];
+    for row in counts {
+        for (j, count) in row.iter().enumerate() {
+            columns[j] += count;
+        }
+    }
+    (rows, columns)
+}
+
+/**
+    * Function to scale the counts of the boundary transitions, the row of the start and the column of the end
+    * Below 1 the end is less likely after every letter so names grow longer, above 1 they get shorter.
+    * The start row is scaled as a whole, so its first letters only get flatter (below 1) or sharper (above 1)
+    * against the smoothing and never change order. Favouring particular first letters is left to the start bias of SampleOptions.
+    * The counts between letters are left alone, and a weight of 1 changes nothing
+**/
+pub fn weight_boundary_counts(counts: &mut [Vec<f64>], weight: f64, alphabet: &Alphabet) {
+    let end = alphabet.end_index();
+    for (i, row) in counts.iter_mut().enumerate() {
+        for (j, count) in row.iter_mut().enumerate() {
+            if i == 0 || j == end {
+                *count *= weight;
+            }
+        }
+    }
+}
+
+/**
+    * Function to turn a matrix of raw counts into probabilities
+    * Rows with nothing in them are left as zeros, the alphabet follows from the size of the matrix
+    * 1. Add the smoothing value to every cell
+    * 2. Reset the cells the alphabet forbids to 0.0; we never want an empty name to be generated
+    * 3. Divide each cell by the total of its row
+**/
+pub fn normalize_counts(counts: &[Vec<f64>], smoothing: f64) -> Vec<Vec<f64>> {
+    normalize_counts_in(counts, smoothing, &Alphabet::for_size(counts.len()))
+}
+
+/**
+    * Function to turn a matrix of raw counts into probabilities like normalize_counts, with a given alphabet
+    * The size alone can't tell which letters are excluded, their rows and columns stay zero here
+**/
+pub fn normalize_counts_in(counts: &[Vec<f64>], smoothing: f64, alphabet: &Alphabet) -> Vec<Vec<f64>> {
+    let mut matrix: Vec<Vec<f64>> = counts.iter().enumerate()
+        .map(|(i, row)| row.iter().enumerate()
+            .map(|(j, count)| if alphabet.is_allowed(i, j) { count + smoothing } else { 0.0 })
+            .collect())
+        .collect();
+    for row in matrix.iter_mut() {
+        let total: f64 = row.iter().sum();
+        if total > 0.0 {
+            row.iter_mut().for_each(|p| *p /= total);
+        }
+    }
+    matrix
+}
+
+/**
+    * Function to create the bigram matrix
+    * 1. Count the bigrams of every name into a matrix
+    * 2. Smooth and normalize each row of counts into probabilities
+**/
+pub fn create_bigram_matrix(names: &[String], smoothing: f64) -> Vec<Vec<f64>> {
+    normalize_counts(&count_matrix(names), smoothing)
+}
+
+/**
+    * Function to take in a row of the porbablity matrix
+    * and sample it as a multinomial distribution to return
+    * the nindex of the next character
+    * Returns None if no weight of the row is above zero
+**/
+pub fn sample_next_char(probablities: &[f64], rng: &mut impl Rng) -> Option<usize> {
+    // weighted index dist
+    let dist = WeightedIndex::new(probablities).ok()?;
+    Some(dist.sample(rng))
+}
+
+/**
+    * Function to convert an int to a char
+**/
+pub fn int_to_char(index: usize) -> char {
+    if index == 0 {
+        return '.';
+    }
+    (index as u8 + 96) as char
+}
+
+/**
+    * Function to convert a char to its index in the matrix
+    * Returns None for characters outside of the dot and a-z
+**/
+pub fn char_to_int(c: char) -> Option<usize> {
+    match c {
+        '.' => Some(0),
+        'a'..='z' => Some(c as usize - 96),
+        _ => None,
+    }
+}
+
+/**
+    * Cacluate the likelihood of a word from the bigram matrix
+    * The word is run through clean_name first, so raw input like "Zelda" can be scored directly
+    * Characters outside of the alphabet can never be generated, so they give a likelihood of 0
+**/
+pub fn likelihood_of_word(word: &str, bigram_matrix: &[Vec<f64>]) -> f64 {
+    transition_probabilities(word, bigram_matrix).product()
+}
+
+/**
+    * Cacluate the log likelihood of a word from the bigram matrix
+    * Summing the natural log of each transition avoids the underflow of multiplying probabilities
+    * Like likelihood_of_word the word is cleaned first
+**/
+pub fn log_likelihood_of_word(word: &str, bigram_matrix: &[Vec<f64>]) -> f64 {
+    transition_probabilities(word, bigram_matrix).map(f64::ln).sum()
+}
+
+/**
+    * Cacluate the log likelihood of a word, scoring only the transitions picked by the boundary mode
+**/
+pub fn log_likelihood_with(word: &str, bigram_matrix: &[Vec<f64>], mode: BoundaryMode) -> f64 {
+    log_likelihood_floored(word, bigram_matrix, mode, 0.0)
+}
+
+/**
+    * Cacluate the log likelihood of a word like log_likelihood_with, raising every probability below epsilon to epsilon
+    * This keeps the score of a word through a forbidden or unseen transition finite, so words can still be ranked.
+    * The tradeoff is that the floored probabilities no longer sum to 1, and the cost of an impossible transition
+    * becomes a tunable -ln(epsilon) instead of infinity. Only the score changes, the matrix used for generation is untouched
+**/
+pub fn log_likelihood_floored(word: &str, bigram_matrix: &[Vec<f64>], mode: BoundaryMode, epsilon: f64) -> f64 {
+    let probabilities: Vec<f64> = transition_probabilities(word, bigram_matrix).map(|p| p.max(epsilon)).collect();
+    match mode {
+        BoundaryMode::Inclusive => probabilities.iter().map(|p| p.ln()).sum(),
+        BoundaryMode::Interior if probabilities.len() > 2 => {
+            probabilities[1..probabilities.len() - 1].iter().map(|p| p.ln()).sum()
+        }
+        BoundaryMode::Interior => 0.0,
+    }
+}
+
+/**
+    * Function to count how many bigrams of a word are scored in a boundary mode
+    * This is what per character scores are normalized by
+**/
+pub fn scored_bigrams(word: &str, mode: BoundaryMode) -> usize {
+    scored_bigrams_in(word, &Alphabet::default(), mode)
+}
+
+/**
+    * Function to count the scored bigrams of a word cleaned for an alphabet, where spaces may count
+**/
+fn scored_bigrams_in(word: &str, alphabet: &Alphabet, mode: BoundaryMode) -> usize {
+    let bigrams = clean_name_in(word, alphabet).chars().count() - 1;
+    match mode {
+        BoundaryMode::Inclusive => bigrams,
+        BoundaryMode::Interior => bigrams.saturating_sub(2),
+    }
+}
+
+/**
+    * The score of a word under the bigram matrix
+    * The per character scores divide by the scored bigrams, so names of any length compare
+**/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WordScore {
+    // natural log probability of the scored transitions
+    pub log_probability: f64,
+    // how many bigrams were scored
+    pub bigrams: usize,
+    // negative log likelihood per scored bigram, in nats
+    pub nll_per_char: f64,
+    // negative log likelihood per scored bigram, in log10 units
+    pub nll_per_char_log10: f64,
+}
+
+/**
+    * Function to score a word, including the start and end transitions
+**/
+pub fn score_word(word: &str, bigram_matrix: &[Vec<f64>]) -> WordScore {
+    score_word_with(word, bigram_matrix, BoundaryMode::Inclusive)
+}
+
+/**
+    * Function to score a word, scoring only the transitions picked by the boundary mode
+    * A word with nothing to score gets a per character score of zero
+**/
+pub fn score_word_with(word: &str, bigram_matrix: &[Vec<f64>], mode: BoundaryMode) -> WordScore {
+    score_word_floored(word, bigram_matrix, mode, 0.0)
+}
+
+/**
+    * Function to score a word with probabilities floored at epsilon, like log_likelihood_floored
+**/
+pub fn score_word_floored(word: &str, bigram_matrix: &[Vec<f64>], mode: BoundaryMode, epsilon: f64) -> WordScore {
+    let log_probability = log_likelihood_floored(word, bigram_matrix, mode, epsilon);
+    let bigrams = scored_bigrams_in(word, &Alphabet::for_size(bigram_matrix.len()), mode);
+    let nll_per_char = -log_probability / bigrams.max(1) as f64;
+    WordScore { log_probability, bigrams, nll_per_char, nll_per_char_log10: nll_per_char / std::f64::consts::LN_10 }
+}
+
+/**
+    * Function to look up the probability of every bigram of a cleaned word
+    * The boundaries and the case of the letters follow the alphabet that fits the size of the matrix
+**/
+fn transition_probabilities<'a>(word: &str, bigram_matrix: &'a [Vec<f64>]) -> impl Iterator<Item = f64> + 'a {
+    let alphabet = Alphabet::for_size(bigram_matrix.len());
+    let indices = alphabet.name_indices(&clean_name_in(word, &alphabet));
+    (1..indices.len()).map(move |i| {
+        match (indices[i - 1], indices[i]) {
+            (Some(first), Some(second)) => bigram_matrix[first][second],
+            _ => 0.0,
+        }
+    })
+}
@@ -1,161 +1,1773 @@
-use std::collections::HashMap;
-use rand::distributions::{Distribution, WeightedIndex};
-use rand::thread_rng;
-
-/**
+/*!
     * A simple rust script implementing a bigram language model
  **/
 
+use std::f64::consts::LN_10;
+use std::fmt::Display;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "progress")]
+use indicatif::{ProgressBar, ProgressStyle};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use bigram::{WordScore, clean_name_with, clean_names_with, count_matrix_weighted_with, filter_by_length, filter_to_alphabet_in, read_corpus_with, score_word_floored, score_word_with, Alphabet, BigramError, BigramModel, BoundaryMode, CleanOptions, NameWeighting, SampleOptions, SmoothingMethod, NameSource, MAX_ATTEMPTS};
+use bigram::attribution::{bigram_sources, covering_names};
+use bigram::chain::{effective_bigrams, expected_name_length, joint_bigram_entropy, stationary_distribution};
+use bigram::eval::{bits_per_character, bootstrap_perplexity, ks_critical_value, learning_curve, length_ks_statistic, perplexity, real_names, train_test_split};
+use bigram::mixture::{LengthBuckets, ModelMixture};
+use bigram::predicate::{parse_predicate, parse_predicate_in};
+use bigram::protocol::{parse_request, Request};
+use bigram::similarity::{near_duplicate_clusters, NameIndex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Serialize;
+
+// seed of the held-out split used by eval
+const SPLIT_SEED: u64 = 0;
+// width of the interval reported by eval --bootstrap
+const BOOTSTRAP_CONFIDENCE: f64 = 0.95;
+// shares of the training names eval --learning-curve trains on, in tenths
+const LEARNING_CURVE_STEPS: usize = 10;
+// seed and number of names generated by selftest on both sides of the round trip
+const SELFTEST_SEED: u64 = 42;
+const SELFTEST_NAMES: usize = 20;
+// largest difference between a saved and loaded probability selftest accepts
+const SELFTEST_TOLERANCE: f64 = 1e-12;
+// why generate refuses --top-n-by-likelihood, its names are written as they are drawn
+const GENERATE_RANKING: &str = "generate writes names as they are drawn, --top-n-by-likelihood and --candidates only rank the names of the demo";
+// why generate refuses --unique with --stream, a model out of new names would spend MAX_ATTEMPTS on every draw
+const STREAM_UNIQUE: &str = "--unique can't be used with --stream, an endless stream would have to remember every name it printed";
+// bigrams and generated names listed in a --report
+const REPORT_TOP_K: usize = 10;
+const REPORT_SAMPLE: usize = 10;
+// longest name searched for when sampling can't reach a --suffix
+const AFFIX_SEARCH_LEN: usize = 20;
+
+// a check a generated name has to pass, names are resampled until they pass every filter
+type NameFilter = Box<dyn Fn(&str) -> bool>;
+
+#[derive(Parser, Debug)]
+#[command(about = "A bigram language model for generating names")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Write the probability matrix as a PNG heatmap (needs the `png` feature)
+    #[arg(long, global = true, value_name = "PATH")]
+    export_png: Option<PathBuf>,
+
+    /// Write the probability matrix as a LaTeX tabular, with the decimal places of --precision
+    #[arg(long, global = true, value_name = "PATH")]
+    export_latex: Option<PathBuf>,
+
+    /// Shade every cell of --export-latex by its probability with \cellcolor, which needs the xcolor package
+    #[arg(long, global = true, requires = "export_latex")]
+    latex_color: bool,
+
+    /// Write the probability matrix with one byte per probability, readable by --load
+    #[arg(long, global = true, value_name = "PATH")]
+    export_quantized: Option<PathBuf>,
+
+    /// Use the unigram distribution for characters never seen as a context in training
+    #[arg(long, global = true)]
+    unigram_backoff: bool,
+
+    /// Compose decomposed accents (NFC) before cleaning names (needs the `unicode` feature)
+    #[arg(long, global = true)]
+    normalize_unicode: bool,
+
+    /// Decompress corpus files with gzip, done anyway for files ending in .gz (needs the `gzip` feature)
+    #[arg(long, global = true)]
+    gzip: bool,
+
+    /// Keep upper case letters apart from lower case ones, which about doubles the alphabet and wants a large corpus
+    #[arg(long, global = true)]
+    case_sensitive: bool,
+
+    /// Keep one space between the words of a line, so the model can learn and generate phrases like "mary jane"
+    #[arg(long, global = true)]
+    keep_spaces: bool,
+
+    /// Strip a character from every name and drop its row and column from the alphabet, e.g. `z` (repeatable)
+    #[arg(long, global = true, value_name = "CHAR")]
+    exclude_char: Vec<char>,
+
+    /// Drop training names shorter than this, not counting the boundary
+    #[arg(long, global = true, value_name = "N")]
+    min_name_len: Option<usize>,
+
+    /// Drop training names longer than this, not counting the boundary
+    #[arg(long, global = true, value_name = "N")]
+    max_name_len: Option<usize>,
+
+    /// Down-weight common training names, by 1/freq (linear) or 1/sqrt(freq) per occurrence
+    #[arg(long, global = true, value_enum, value_name = "RULE", num_args = 0..=1, default_missing_value = "linear")]
+    inverse_freq: Option<InverseFrequency>,
+
+    /// How probabilities are estimated from the counts
+    #[arg(long, global = true, value_enum, default_value_t = Smoothing::Additive)]
+    smoothing: Smoothing,
+
+    /// Weight of the observed bigrams against the unigram distribution for `--smoothing interpolated`
+    #[arg(long, global = true, default_value_t = 0.9, value_parser = parse_lambda)]
+    lambda: f64,
+
+    /// Observed bigrams that give a row half its weight on them for `--smoothing confidence`
+    #[arg(long, global = true, value_name = "K", default_value_t = 10.0, value_parser = parse_confidence_k)]
+    confidence_k: f64,
+
+    /// Scale the counts out of the start and into the end before normalizing, below 1 gives longer names.
+    /// Only the name length and how flat the first letters are against the smoothing change, use --start-bias to favour letters
+    #[arg(long, global = true, value_name = "W", default_value_t = 1.0, value_parser = parse_boundary_weight)]
+    boundary_weight: f64,
+
+    /// Multiply the probability of a transition after smoothing, e.g. `q:x:0` forbids x after q (repeatable)
+    #[arg(long, global = true, value_name = "PREV:NEXT:MULT", value_parser = parse_constraint)]
+    constraint: Vec<(char, char, f64)>,
+
+    /// Boundary tokens of a new model, one shared token like `.` or a start and end like `^$`
+    #[arg(long, global = true, value_name = "TOKENS", value_parser = parse_boundary_tokens)]
+    boundary_tokens: Option<Alphabet>,
+
+    /// Number of names to generate
+    #[arg(long, global = true, default_value_t = 5)]
+    count: usize,
+
+    /// Only keep generated names matching this regex (needs the `regex` feature)
+    #[arg(long = "match", global = true, value_name = "REGEX")]
+    pattern: Option<String>,
+
+    /// Regenerate names until they satisfy an expression like `len>=4 && starts=m && !contains=xx`
+    #[arg(long, global = true, value_name = "EXPR", value_parser = predicate_expression)]
+    repeat_until: Option<String>,
+
+    /// Generate candidates and only keep this many with the best likelihood (not for generate)
+    #[arg(long, global = true, value_name = "N", value_parser = parse_top_n)]
+    top_n_by_likelihood: Option<usize>,
+
+    /// How many candidates to generate for --top-n-by-likelihood (default 10 times N)
+    #[arg(long, global = true, value_name = "M", requires = "top_n_by_likelihood")]
+    candidates: Option<usize>,
+
+    /// Reject generated names fewer than this many edits away from a training name
+    #[arg(long, global = true, value_name = "N")]
+    min_edit_distance: Option<usize>,
+
+    /// Never sample the boundary as the first character, even if the matrix allows it
+    #[arg(long, global = true)]
+    sanitize: bool,
+
+    /// Multiply the chance of names starting with a letter, e.g. `m:3.0` (repeatable)
+    #[arg(long, global = true, value_name = "CHAR:WEIGHT", value_parser = parse_start_bias)]
+    start_bias: Vec<(char, f64)>,
+
+    /// Only sample steps more likely than this, falling back to the whole row when none is
+    #[arg(long, global = true, value_name = "P", default_value_t = 0.0, value_parser = parse_probability)]
+    min_step_prob: f64,
+
+    /// Raise every step probability to 1 / T before sampling, below 1 gives safer names and above 1 wilder ones
+    #[arg(long, global = true, value_name = "T", value_parser = parse_temperature)]
+    temperature: Option<f64>,
+
+    /// Only sample from the most likely steps that together hold this share of the row, after --temperature
+    #[arg(long, global = true, value_name = "P", value_parser = parse_top_p)]
+    top_p: Option<f64>,
+
+    /// Train a model per range of name lengths split at these upper bounds, e.g. `3,6` for 1-3, 4-6 and 7 or more,
+    /// and generate each name from one of them, picked by its share of the corpus
+    #[arg(long, global = true, value_name = "BOUNDS", value_delimiter = ',')]
+    length_buckets: Vec<usize>,
+
+    /// Start every generated name with these letters and sample the rest after them
+    #[arg(long, global = true, value_name = "LETTERS")]
+    prefix: Option<String>,
+
+    /// Only keep generated names ending in these letters, falls back to the most likely such name when none is sampled
+    #[arg(long, global = true, value_name = "LETTERS")]
+    suffix: Option<String>,
+
+    /// Never repeat a name within one batch of generated names
+    #[arg(long, global = true)]
+    unique: bool,
+
+    /// Seed of the random generator, to repeat an earlier run (printed to stderr when left out)
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    // the seed of this run, from --seed or read from the clock the first time a generator is made
+    #[arg(skip)]
+    run_seed: OnceLock<u64>,
+
+    /// Score transitions with a probability below this as this, so forbidden transitions don't give infinite scores
+    #[arg(long, global = true, value_name = "EPS", value_parser = parse_epsilon)]
+    epsilon: Option<f64>,
+
+    /// Leave the start and end transitions out of reported likelihoods
+    #[arg(long, global = true)]
+    interior_only: bool,
+
+    /// Show progress bars on stderr for long runs, when stdout is a terminal (needs the `progress` feature)
+    #[arg(long, global = true)]
+    progress: bool,
+
+    /// Decimal places of every printed score, probability and perplexity
+    #[arg(long, global = true, value_name = "N", default_value_t = 4)]
+    precision: usize,
+
+    /// Write a Markdown summary of the trained model to this file
+    #[arg(long, global = true, value_name = "PATH")]
+    report: Option<PathBuf>,
+
+    /// Check the options and print what would run, without training
+    #[arg(long, global = true)]
+    dry_run: bool,
+}
+
+/**
+    * A progress bar on stderr, or a spinner when the total isn't known up front
+    * Nothing is drawn unless it is enabled and stdout is a terminal, so piped output stays clean
+**/
+struct Progress {
+    #[cfg(feature = "progress")]
+    bar: Option<ProgressBar>,
+}
+
+#[cfg(feature = "progress")]
+impl Progress {
+    fn new(enabled: bool, total: Option<usize>, message: &'static str) -> Progress {
+        use std::io::IsTerminal;
+        if !enabled || !std::io::stdout().is_terminal() {
+            return Progress { bar: None };
+        }
+        let bar = match total {
+            Some(total) => ProgressBar::new(total as u64)
+                .with_style(ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len} ({eta})").expect("the template is valid")),
+            None => ProgressBar::new_spinner()
+                .with_style(ProgressStyle::with_template("{spinner} {msg}: {pos}").expect("the template is valid")),
+        };
+        Progress { bar: Some(bar.with_message(message)) }
+    }
+
+    fn inc(&self, n: usize) {
+        if let Some(bar) = &self.bar {
+            bar.inc(n as u64);
+        }
+    }
+
+    fn finish(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(not(feature = "progress"))]
+impl Progress {
+    fn new(enabled: bool, _total: Option<usize>, _message: &'static str) -> Progress {
+        if enabled {
+            fail("--progress needs the progress feature, rebuild with `--features progress`");
+        }
+        Progress {}
+    }
+
+    fn inc(&self, _n: usize) {}
+
+    fn finish(&self) {}
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum InverseFrequency {
+    Linear,
+    Sqrt,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Smoothing {
+    /// Add one to every count
+    Additive,
+    /// Mix every row with the unigram distribution by --lambda
+    Interpolated,
+    /// Mix every row with the unigram distribution by how many bigrams it observed, see --confidence-k
+    Confidence,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Mix {
+    /// Pick a random model for every name, in proportion to --weights if given
+    Random,
+}
+
+impl Cli {
+    /**
+        * Function to get the cleaning options, exiting if one needs a missing feature
+    **/
+    #[cfg(feature = "unicode")]
+    fn clean_options(&self) -> CleanOptions {
+        CleanOptions { normalize_unicode: self.normalize_unicode, case_sensitive: self.case_sensitive, keep_spaces: self.keep_spaces, exclude: self.excluded_chars() }
+    }
+
+    #[cfg(not(feature = "unicode"))]
+    fn clean_options(&self) -> CleanOptions {
+        if self.normalize_unicode {
+            fail("--normalize-unicode needs the unicode feature, rebuild with `--features unicode`");
+        }
+        CleanOptions { case_sensitive: self.case_sensitive, keep_spaces: self.keep_spaces, exclude: self.excluded_chars() }
+    }
+
+    /**
+        * Function to get the alphabet of a new model from the boundary tokens, the case, the spaces and the excluded characters
+    **/
+    fn alphabet(&self) -> Alphabet {
+        self.boundary_tokens.unwrap_or_default().with_case(self.case_sensitive).with_spaces(self.keep_spaces).with_excluded(&self.excluded_chars())
+    }
+
+    /**
+        * Function to get the characters of --exclude-char, lower cased unless case sensitive
+    **/
+    fn excluded_chars(&self) -> Vec<char> {
+        match self.case_sensitive {
+            true => self.exclude_char.clone(),
+            false => self.exclude_char.iter().flat_map(|c| c.to_lowercase()).collect(),
+        }
+    }
+
+    /**
+        * Function to format a score, probability or perplexity with the decimal places of --precision
+    **/
+    fn decimals(&self, value: f64) -> String {
+        format!("{:.*}", self.precision, value)
+    }
+
+    fn sample_options(&self) -> SampleOptions {
+        SampleOptions {
+            sanitize: self.sanitize,
+            start_bias: self.start_bias.clone(),
+            prefix: self.prefix.clone().unwrap_or_default(),
+            min_step_prob: self.min_step_prob,
+            temperature: self.temperature,
+            top_p: self.top_p,
+            unique: self.unique,
+        }
+    }
+
+    fn smoothing_method(&self) -> SmoothingMethod {
+        match self.smoothing {
+            Smoothing::Additive => SmoothingMethod::Additive,
+            Smoothing::Interpolated => SmoothingMethod::Interpolated(self.lambda),
+            Smoothing::Confidence => SmoothingMethod::ConfidenceWeighted(self.confidence_k),
+        }
+    }
+
+    fn weighting(&self) -> NameWeighting {
+        match self.inverse_freq {
+            None => NameWeighting::Frequency,
+            Some(InverseFrequency::Linear) => NameWeighting::InverseFrequency,
+            Some(InverseFrequency::Sqrt) => NameWeighting::InverseSqrtFrequency,
+        }
+    }
+
+    /**
+        * Function to make a random generator of the run
+        * Without --seed the seed comes from the clock once and is printed so the run can be repeated,
+        * every generator of the run starts from that same seed like they do with --seed
+    **/
+    fn rng(&self) -> StdRng {
+        let seed = *self.run_seed.get_or_init(|| self.seed.unwrap_or_else(|| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            let seed = now.as_nanos() as u64;
+            eprintln!("seed={}", seed);
+            seed
+        }));
+        StdRng::seed_from_u64(seed)
+    }
+
+    /**
+        * Function to score a word under a model with the scoring options
+    **/
+    fn score(&self, word: &str, model: &BigramModel) -> WordScore {
+        score_word_floored(word, &model.matrix, self.boundary_mode(), self.epsilon.unwrap_or(0.0))
+    }
+
+    fn boundary_mode(&self) -> BoundaryMode {
+        if self.interior_only {
+            BoundaryMode::Interior
+        } else {
+            BoundaryMode::Inclusive
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    /// Saved models to generate from
+    #[arg(long, value_name = "PATH", num_args = 1.., required = true)]
+    load: Vec<PathBuf>,
+    /// How to pick the model of each name
+    #[arg(long, value_enum, default_value = "random")]
+    mix: Mix,
+    /// Relative chance of picking each model, one per --load
+    #[arg(long, value_name = "W", num_args = 1..)]
+    weights: Vec<f64>,
+    /// Only print the names, not the model each came from or a table
+    #[arg(long)]
+    quiet: bool,
+    /// Print an aligned table with the score of each name and whether it is in the training corpus
+    #[arg(long)]
+    table: bool,
+    /// Output format, json prints one object with the table columns per name
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+    /// Keep printing names until interrupted or the output is closed, instead of --count names (not with --unique)
+    #[arg(long, conflicts_with = "table")]
+    stream: bool,
+    /// Wait this long after every streamed name
+    #[arg(long, value_name = "MS", requires = "stream")]
+    delay_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+// a row of the generate table
+#[derive(Serialize)]
+struct GeneratedName {
+    index: usize,
+    name: String,
+    // -log10(likelihood) per scored bigram
+    nll_per_char: f64,
+    // not one of the training names
+    novel: bool,
+    model: String,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print statistics about the training corpus and the trained model
+    Stats {
+        /// Also compare the long-run letter frequencies of the chain with the ones seen in training
+        #[arg(long)]
+        stationary: bool,
+        /// Also print the entropy of the joint bigram distribution and the effective number of bigrams, 2 to it
+        #[arg(long)]
+        effective_bigrams: bool,
+    },
+    /// Print the letters the model can hardly generate and the letters no name starts with
+    Coverage {
+        /// Report letters whose probability summed over every row is below this
+        #[arg(long, default_value_t = 0.05)]
+        threshold: f64,
+    },
+    /// Print the score of words, -log10(likelihood) per scored bigram
+    Score {
+        /// Words to score
+        #[arg(required = true)]
+        words: Vec<String>,
+        /// Also print the probability of every transition and the running log probability
+        #[arg(long)]
+        verbose: bool,
+    },
+    /// Print the bigrams with the highest and lowest pointwise mutual information
+    Pmi {
+        /// How many bigrams to print at each end
+        #[arg(short, default_value_t = 10)]
+        k: usize,
+    },
+    /// Print the raw bigram counts, before smoothing and normalization
+    DumpCounts {
+        /// Write every transition with its count and smoothed probability to this CSV file instead
+        #[arg(long, value_name = "PATH")]
+        csv: Option<PathBuf>,
+    },
+    /// Print the training names the model finds most and least likely
+    Extremes {
+        /// How many names to print at each end
+        #[arg(short, default_value_t = 10)]
+        k: usize,
+    },
+    /// Print clusters of training names within a few edits of each other, which skew the counts of a scraped corpus
+    Duplicates {
+        /// Largest edit distance between two names of a cluster
+        #[arg(long, default_value_t = 1)]
+        max_edit: usize,
+    },
+    /// Print the training names behind each bigram of a name and a small set of them covering it
+    Explain {
+        /// Name to explain, cleaned like the training names
+        name: String,
+        /// How many example names to print for each bigram
+        #[arg(short, default_value_t = 5)]
+        k: usize,
+    },
+    /// Print the single most probable name the model can produce
+    Best {
+        /// Longest name to consider
+        #[arg(long, default_value_t = 12)]
+        max_len: usize,
+    },
+    /// Report the perplexity and bits per character of held-out names
+    Eval {
+        /// File of names to evaluate on, instead of holding out part of the training corpus
+        corpus: Option<PathBuf>,
+        /// Fraction of the training corpus held out for evaluation when no corpus is given
+        #[arg(long, default_value_t = 0.1, value_parser = parse_test_fraction)]
+        test_fraction: f64,
+        /// Also report a 95% interval of the perplexity from this many resamples of the test names
+        #[arg(long, value_name = "B")]
+        bootstrap: Option<usize>,
+        /// Also generate --count names and report how many are real names of this file
+        #[arg(long, value_name = "PATH")]
+        reference: Option<PathBuf>,
+        /// Also report the perplexity of every smoothing method on the same held-out names
+        #[arg(long)]
+        compare_smoothing: bool,
+        /// Also report the held-out perplexity of models trained on 10%, 20%, ... 100% of the training names
+        #[arg(long)]
+        learning_curve: bool,
+    },
+    /// Show what cleaning does to a corpus file before training on it
+    Preview {
+        /// File with one name per line
+        corpus: PathBuf,
+        /// How many original -> cleaned pairs to print
+        #[arg(long, default_value_t = 10)]
+        sample: usize,
+    },
+    /// Generate names from saved models, sampling each name from one of them
+    Generate(GenerateArgs),
+    /// Answer gen and score requests over TCP, one request per line
+    Serve {
+        /// Saved model to serve instead of training on the bundled corpus
+        #[arg(long, value_name = "PATH")]
+        load: Option<PathBuf>,
+        /// Port to listen on, on localhost
+        #[arg(long, default_value_t = 7878)]
+        port: u16,
+    },
+    /// Check that a model trained on the bundled corpus survives a save and load unchanged
+    Selftest,
+    /// Train a model on a corpus file, optionally adding to a saved model
+    Train {
+        /// File with one name per line
+        corpus: PathBuf,
+        /// Add the counts of the corpus to this saved model instead of starting fresh
+        #[arg(long, value_name = "PATH")]
+        load: Option<PathBuf>,
+        /// Write the trained model to this file
+        #[arg(long, value_name = "PATH")]
+        save: Option<PathBuf>,
+    },
+}
+
 fn main() {
-    println!(" Welcome to the bigram name model!");
+    let cli = Cli::parse();
+    if cli.dry_run {
+        dry_run(&cli);
+        return;
+    }
+    // previewing a corpus doesn't need a model
+    if let Some(Command::Preview { corpus, sample }) = &cli.command {
+        preview(corpus, *sample, cli.gzip, &cli.clean_options());
+        return;
+    }
 
-    //load in the names file
-    let names = include_str!("../files/names.txt");
-    // split on new lines
-    let names: Vec<&str> = names.split("\n").collect();
-    let cleaned_names: Vec<String> = names.iter().map(|name| clean_name(name)).collect();
+    //load in the names file, the bundled one unless training on another corpus
+    let names: Vec<String> = match &cli.command {
+        Some(Command::Train { corpus, .. }) => read_corpus_with(corpus, cli.gzip).unwrap_or_else(|e| fail(e)),
+        // split on new lines
+        _ => include_str!("../files/names.txt").split('\n').map(String::from).collect(),
+    };
+    let cleaned_names = clean_names_with(&names, &cli.clean_options());
+    let (cleaned_names, outside_alphabet) = filter_to_alphabet_in(cleaned_names, &cli.alphabet());
+    if outside_alphabet > 0 {
+        eprintln!("warning: skipped {} names with letters outside the alphabet", outside_alphabet);
+    }
+    let (cleaned_names, filtered) = filter_by_length(cleaned_names, cli.min_name_len.unwrap_or(0), cli.max_name_len.unwrap_or(usize::MAX));
     // now we need to restructure this into a matrix of bigram counts
     // we can then use this to calculate the probability of each bigram
     // and then use this to generate new names
-    let bigram_matrix = create_bigram_matrix(&cleaned_names, 1.0);
-    //println!("{:?}", &bigram_matrix[..3]);
-    // print the first few names and the neg log likelihood
-    for name in &cleaned_names[..5] {
-        println!("name: {}, -log(likelihood): {}", name, -1.0*likelihood_of_word(name, &bigram_matrix).log10()/(name.len() as f64));
+    let model = match &cli.command {
+        Some(Command::Train { load: Some(path), .. }) => {
+            // grow the saved model with the new names
+            let mut model = BigramModel::load(path).unwrap_or_else(|e| fail(e));
+            if cli.boundary_tokens.is_some_and(|tokens| (tokens.start, tokens.end) != (model.alphabet.start, model.alphabet.end)) {
+                fail(format!("{} was saved with other boundary tokens", path.display()));
+            }
+            if cli.case_sensitive != model.alphabet.case_sensitive {
+                let saved = if model.alphabet.case_sensitive { "with" } else { "without" };
+                fail(format!("{} was saved {} --case-sensitive, train it the same way", path.display(), saved));
+            }
+            if cli.keep_spaces != model.alphabet.spaces {
+                let saved = if model.alphabet.spaces { "with" } else { "without" };
+                fail(format!("{} was saved {} --keep-spaces, train it the same way", path.display(), saved));
+            }
+            if cli.alphabet().excluded != model.alphabet.excluded {
+                let saved: String = model.alphabet.excluded_chars().into_iter().collect();
+                fail(format!("{} was saved excluding \"{}\", train it with the same --exclude-char", path.display(), saved));
+            }
+            if cli.boundary_weight != model.boundary_weight {
+                fail(format!("{} was saved with --boundary-weight {}, train it the same way", path.display(), model.boundary_weight));
+            }
+            if cleaned_names.is_empty() {
+                fail(BigramError::EmptyCorpus);
+            }
+            // the saved counts were weighted when they were trained
+            let spinner = Progress::new(cli.progress, None, "counting names");
+            let counts = count_matrix_weighted_with(&cleaned_names, cli.weighting(), &model.alphabet, |n| spinner.inc(n));
+            spinner.finish();
+            // the counts are raw, the saved boundary weight goes on all of them when the matrix is rebuilt
+            model.add_counts(&counts);
+            model.renormalize();
+            model.apply_smoothing_method(cli.smoothing_method());
+            apply_training_options(&cli, model)
+        }
+        _ => train(&cli, &cleaned_names),
+    };
+    // trained once for the demo and the report alike
+    let buckets = train_buckets(&cli, &cleaned_names);
+
+    match &cli.command {
+        None => demo(&cli, &model, buckets.as_ref(), &cleaned_names),
+        Some(Command::Stats { stationary, effective_bigrams: effective }) => {
+            println!("corpus lines: {}", names.len());
+            println!("outside the alphabet: {}", outside_alphabet);
+            println!("filtered by length: {}", filtered);
+            println!("training names: {}", cleaned_names.len());
+            let letters: usize = cleaned_names.iter().map(|name| name.chars().count() - 2).sum();
+            println!("mean training name length: {:.4}", letters as f64 / cleaned_names.len().max(1) as f64);
+            print_model_stats(&model);
+            if *effective {
+                let possible = (0..model.size()).flat_map(|i| (0..model.size()).map(move |j| (i, j)))
+                    .filter(|(i, j)| model.alphabet.is_allowed(*i, *j))
+                    .count();
+                println!("joint bigram entropy: {} bits", cli.decimals(joint_bigram_entropy(&model.matrix)));
+                println!("effective bigrams: {} of {} possible", cli.decimals(effective_bigrams(&model.matrix)), possible);
+            }
+            if *stationary {
+                print_stationary(&cli, &model);
+            }
+        }
+        Some(Command::Coverage { threshold }) => print_coverage(&cli, &model, *threshold),
+        Some(Command::Score { words, verbose }) => print_scores(&cli, &model, words, *verbose),
+        Some(Command::Pmi { k }) => print_pmi(&cli, &model, *k),
+        Some(Command::DumpCounts { csv }) => dump_counts(&model, csv.as_deref()),
+        Some(Command::Extremes { k }) => print_extremes(&cli, &model, &cleaned_names, *k),
+        Some(Command::Duplicates { max_edit }) => print_duplicates(&cleaned_names, *max_edit),
+        Some(Command::Explain { name, k }) => explain(&model, &cleaned_names, &clean_name_with(name, &cli.clean_options()), *k),
+        Some(Command::Best { max_len }) => match model.most_likely_name(*max_len) {
+            Some((name, log_probability)) => println!("{} (ln p = {})", name, cli.decimals(log_probability)),
+            None => println!("no name of at most {} letters can be generated", max_len),
+        },
+        Some(Command::Eval { corpus, test_fraction, bootstrap, reference, compare_smoothing, learning_curve }) => {
+            evaluate(&cli, &cleaned_names, corpus.as_deref(), *test_fraction, *bootstrap, reference.as_deref(), *compare_smoothing, *learning_curve)
+        }
+        Some(Command::Preview { .. }) => unreachable!("preview runs before training"),
+        Some(Command::Generate(args)) => generate(&cli, &cleaned_names, args),
+        Some(Command::Serve { load, port }) => {
+            let model = match load {
+                Some(path) => apply_training_options(&cli, BigramModel::load(path).unwrap_or_else(|e| fail(e))),
+                None => model.clone(),
+            };
+            serve(&cli, &model, &cleaned_names, *port);
+        }
+        Some(Command::Selftest) => selftest(&model),
+        Some(Command::Train { save, .. }) => {
+            println!("trained on {} names", cleaned_names.len());
+            if let Some(path) = save {
+                model.save(path).unwrap_or_else(|e| fail(e));
+                println!("saved model to {}", path.display());
+            }
+        }
     }
 
-    // Sample the matrix a few times
-    for _ in 0..5 {
-        let mut name = String::new();
-        let mut current_char = 0;
-        name.push(int_to_char(current_char));
-        loop {
-            let next_char = sample_next_char(&bigram_matrix[current_char]);
-            current_char = next_char;
-            name.push(int_to_char(next_char));
-            if int_to_char(next_char) == '.' {
-                break;
+    if let Some(path) = &cli.export_png {
+        export_png(&model, path);
+    }
+    if let Some(path) = &cli.export_latex {
+        export_latex(&cli, &model, path);
+    }
+    if let Some(path) = &cli.export_quantized {
+        model.save_quantized(path).unwrap_or_else(|e| fail(e));
+        println!("saved quantized model to {}", path.display());
+    }
+    if let Some(path) = &cli.report {
+        write_report(&cli, &model, buckets.as_ref(), &cleaned_names, path);
+    }
+}
+
+/**
+    * Function to print the parsed options with the settings they resolve to, exiting if any is invalid
+    * Options are only read from the command line, so there is nothing else to merge
+**/
+fn dry_run(cli: &Cli) {
+    println!("{:#?}", cli);
+    println!("weighting: {:?}", cli.weighting());
+    println!("boundary mode: {}", mode_description(cli.boundary_mode()));
+    println!("alphabet: {:?} of {} characters", cli.alphabet(), cli.alphabet().size());
+    match cli.seed {
+        Some(seed) => println!("seed: {}", seed),
+        None => println!("seed: from the clock"),
+    }
+
+    let problems = option_problems(cli);
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("error: {}", problem);
+        }
+        process::exit(1);
+    }
+    println!("dry run: the options are valid, nothing was trained");
+}
+
+/**
+    * Function to collect everything wrong with the options without running anything
+**/
+fn option_problems(cli: &Cli) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut inputs: Vec<&Path> = Vec::new();
+    let mut outputs: Vec<&Path> = Vec::new();
+    match &cli.command {
+        Some(Command::Eval { corpus, reference, .. }) => {
+            inputs.extend(corpus.as_deref());
+            inputs.extend(reference.as_deref());
+        }
+        Some(Command::Preview { corpus, .. }) => inputs.push(corpus),
+        Some(Command::Generate(args)) => {
+            inputs.extend(args.load.iter().map(PathBuf::as_path));
+            if !args.weights.is_empty() && args.weights.len() != args.load.len() {
+                problems.push(format!("{} weights given for {} models", args.weights.len(), args.load.len()));
+            }
+            if cli.top_n_by_likelihood.is_some() {
+                problems.push(GENERATE_RANKING.to_string());
             }
+            if args.stream && cli.unique {
+                problems.push(STREAM_UNIQUE.to_string());
+            }
+        }
+        Some(Command::Serve { load, .. }) => inputs.extend(load.as_deref()),
+        Some(Command::DumpCounts { csv }) => outputs.extend(csv.as_deref()),
+        Some(Command::Train { corpus, load, save }) => {
+            inputs.push(corpus);
+            inputs.extend(load.as_deref());
+            outputs.extend(save.as_deref());
+        }
+        _ => {}
+    }
+    outputs.extend(cli.export_png.as_deref());
+    outputs.extend(cli.export_latex.as_deref());
+    outputs.extend(cli.export_quantized.as_deref());
+    outputs.extend(cli.report.as_deref());
+    for path in inputs {
+        if !path.exists() {
+            problems.push(format!("{} does not exist", path.display()));
+        }
+    }
+    for path in outputs {
+        let directory = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        if !directory.is_dir() {
+            problems.push(format!("can't write {}, {} is not a directory", path.display(), directory.display()));
+        }
+    }
+
+    if let (Some(min), Some(max)) = (cli.min_name_len, cli.max_name_len) {
+        if min > max {
+            problems.push(format!("--min-name-len ({}) is larger than --max-name-len ({})", min, max));
+        }
+    }
+    if let (Some(top_n), Some(candidates)) = (cli.top_n_by_likelihood, cli.candidates) {
+        if candidates < top_n {
+            problems.push(format!("--candidates ({}) must be at least --top-n-by-likelihood ({})", candidates, top_n));
         }
-        println!("Generated name: {}, -log(likelihood): {}", name, -1.0*likelihood_of_word(&name, &bigram_matrix).log10()/(name.len() as f64));
+    }
+    if cli.length_buckets.first() == Some(&0) || cli.length_buckets.windows(2).any(|pair| pair[0] >= pair[1]) {
+        problems.push(format!("--length-buckets {:?} have to be positive and increasing", cli.length_buckets));
+    }
+    for (option, used, feature, enabled) in [
+        ("--export-png", cli.export_png.is_some(), "png", cfg!(feature = "png")),
+        ("--gzip", cli.gzip, "gzip", cfg!(feature = "gzip")),
+        ("--match", cli.pattern.is_some(), "regex", cfg!(feature = "regex")),
+        ("--normalize-unicode", cli.normalize_unicode, "unicode", cfg!(feature = "unicode")),
+        ("--progress", cli.progress, "progress", cfg!(feature = "progress")),
+    ] {
+        if used && !enabled {
+            problems.push(format!("{} needs the {} feature, rebuild with `--features {}`", option, feature, feature));
+        }
+    }
+    problems
+}
+
+/**
+    * Function to train a model on cleaned names with the training options
+**/
+fn train(cli: &Cli, cleaned_names: &[String]) -> BigramModel {
+    // now we need to restructure this into a matrix of bigram counts
+    // we can then use this to calculate the probability of each bigram
+    // and then use this to generate new names
+    train_with(cli, cleaned_names, cli.smoothing_method())
+}
+
+/**
+    * Function to train the models of the --length-buckets on cleaned names, none without the option
+**/
+fn train_buckets(cli: &Cli, cleaned_names: &[String]) -> Option<LengthBuckets> {
+    (!cli.length_buckets.is_empty()).then(|| {
+        LengthBuckets::train(cleaned_names, &cli.length_buckets, |names| Ok(train(cli, names))).unwrap_or_else(|e| fail(e))
+    })
+}
+
+/**
+    * Function to train a model like train, with another smoothing method than the options give
+**/
+fn train_with(cli: &Cli, cleaned_names: &[String], method: SmoothingMethod) -> BigramModel {
+    let spinner = Progress::new(cli.progress, None, "counting names");
+    let model = BigramModel::from_names_with(cleaned_names, cli.weighting(), cli.alphabet(), 1.0, |n| spinner.inc(n));
+    spinner.finish();
+    let mut model = model.unwrap_or_else(|e| fail(e));
+    if cli.boundary_weight != 1.0 {
+        model.apply_boundary_weight(cli.boundary_weight);
+    }
+    model.apply_smoothing_method(method);
+    apply_training_options(cli, model)
+}
+
+/**
+    * Function to apply the options that adjust a model after counting
+**/
+fn apply_training_options(cli: &Cli, mut model: BigramModel) -> BigramModel {
+    if cli.unigram_backoff {
+        model.apply_unigram_backoff();
+    }
+    // constraints go last so nothing renormalizes them away
+    model.apply_constraints(&cli.constraint).unwrap_or_else(|e| fail(e));
+    model
+}
+
+/**
+    * Function to print the perplexity and bits per character of held-out names
+    * Without a corpus a deterministic split of the training names is held out
+**/
+// every report of eval is its own option
+#[allow(clippy::too_many_arguments)]
+fn evaluate(cli: &Cli, cleaned_names: &[String], corpus: Option<&Path>, test_fraction: f64, bootstrap: Option<usize>, reference: Option<&Path>, compare_smoothing: bool, curve: bool) {
+    let (training, test) = match corpus {
+        Some(path) => (cleaned_names.to_vec(), read_eval_corpus(cli, path)),
+        None => {
+            // the split is seeded so runs with different options are compared on the same names
+            let mut rng = StdRng::seed_from_u64(SPLIT_SEED);
+            train_test_split(cleaned_names.to_vec(), test_fraction, &mut rng)
+        }
+    };
+    if test.is_empty() {
+        fail("there are no test names to evaluate on");
+    }
+    let model = train(cli, &training);
+    // single letter names have no interior transitions, so a test set of them has nothing to score
+    let scored = |value: Option<f64>| value.map_or("none, no transitions to score".to_string(), |value| cli.decimals(value));
+    println!("evaluated on {} names", test.len());
+    for mode in [BoundaryMode::Inclusive, BoundaryMode::Interior] {
+        println!("{}:", mode_description(mode));
+        println!("  perplexity: {}", scored(perplexity(&test, &model.matrix, mode)));
+        println!("  bits per character: {}", scored(bits_per_character(&test, &model.matrix, mode)));
+    }
+
+    // one generator for the resamples and the generated names, so a seed replays both
+    let mut rng = cli.rng();
+    if let Some(iterations) = bootstrap {
+        let mode = cli.boundary_mode();
+        match bootstrap_perplexity(&test, &model.matrix, mode, iterations, BOOTSTRAP_CONFIDENCE, &mut rng) {
+            Some(interval) => println!("bootstrap perplexity over {} resamples, {}: {} (95% interval {} to {})",
+                iterations, mode_description(mode), cli.decimals(interval.mean), cli.decimals(interval.lower), cli.decimals(interval.upper)),
+            None => println!("bootstrap needs test names and at least one resample"),
+        }
+    }
+
+    if compare_smoothing {
+        let mode = cli.boundary_mode();
+        println!("perplexity by smoothing method, {}:", mode_description(mode));
+        for method in [SmoothingMethod::Additive, SmoothingMethod::Interpolated(cli.lambda), SmoothingMethod::ConfidenceWeighted(cli.confidence_k)] {
+            println!("  {:?}: {}", method, scored(perplexity(&test, &train_with(cli, &training, method).matrix, mode)));
+        }
+    }
+
+    if curve {
+        let mode = cli.boundary_mode();
+        // shuffled with the split seed, so the shares are the same names on every run
+        let mut shuffled = training.clone();
+        shuffled.shuffle(&mut StdRng::seed_from_u64(SPLIT_SEED));
+        println!("learning curve, {}:", mode_description(mode));
+        println!("{:>6} {:>8} {:>12}", "share", "names", "perplexity");
+        for point in learning_curve(&shuffled, &test, LEARNING_CURVE_STEPS, mode, |names| train(cli, names)) {
+            let share = 100.0 * point.names as f64 / shuffled.len() as f64;
+            println!("{:>5.0}% {:>8} {:>12}", share, point.names, cli.decimals(point.perplexity));
+        }
+    }
+
+    if let Some(path) = reference {
+        let reference: HashSet<String> = read_eval_corpus(cli, path)
+            .iter()
+            .map(|name| name.trim_matches('.').to_string())
+            .collect();
+        let buckets = train_buckets(cli, &training);
+        let generated: Vec<String> = sample_batch(cli, &model, buckets.as_ref(), cleaned_names, cli.count, &mut rng).into_iter().map(|(name, _)| name).collect();
+        let real = real_names(&generated, &reference);
+        println!("reference check over {} generated names: {} real ({:.1}%), {} novel",
+            generated.len(), real, 100.0 * real as f64 / generated.len().max(1) as f64, generated.len() - real);
     }
 }
 
 /**
-    * Function to clean the names
-    * 1. Remove any non-alphabetic characters
-    * 2. Convert to lowercase
-    * 3. Add a dot to the start and end of the name
+    * Function to read and clean a corpus for eval like the training corpus, warning about the names outside the alphabet
+    * One such name would score a probability of 0 and make the perplexity infinite
 **/
-fn clean_name(name: &str) -> String {
-    // remove any non-alphabetic characters
-    let name: String = name.chars().filter(|c| c.is_alphabetic()).collect();
-    // convert to lowercase
-    let name = name.to_lowercase();
-    // add dot to start and end
-    let name = format!(".{}.", name);
-    name
+fn read_eval_corpus(cli: &Cli, path: &Path) -> Vec<String> {
+    let names = clean_names_with(&read_corpus_with(path, cli.gzip).unwrap_or_else(|e| fail(e)), &cli.clean_options());
+    let (names, outside_alphabet) = filter_to_alphabet_in(names, &cli.alphabet());
+    if outside_alphabet > 0 {
+        eprintln!("warning: skipped {} names with letters outside the alphabet", outside_alphabet);
+    }
+    names
 }
 
 /**
-    * Function to count the bigrams
-    * 1. Create a hashmap to store the bigram counts
-    * 2. Iterate over the names
-    * 3. For each name, iterate over the characters
-    * 4. For each character, get the bigram and increment the count
+    * Function to generate names from a mixture of saved models
 **/
-fn count_bigrams(names: &Vec<String>) -> HashMap<String, i32> {
-    let mut bigram_counts = HashMap::new();
-    for name in &names[..] {
-        for i in 0..name.len() - 1 {
-            let bigram = &name[i..i+2];
-            let count = bigram_counts.entry(bigram.to_string()).or_insert(0);
-            *count += 1;
+fn generate(cli: &Cli, cleaned_names: &[String], args: &GenerateArgs) {
+    if cli.top_n_by_likelihood.is_some() {
+        fail(GENERATE_RANKING);
+    }
+    if args.stream && cli.unique {
+        fail(STREAM_UNIQUE);
+    }
+    let models: Vec<BigramModel> = args.load.iter()
+        .map(|path| apply_training_options(cli, BigramModel::load(path).unwrap_or_else(|e| fail(e))))
+        .collect();
+    let mixture = match args.mix {
+        Mix::Random if args.weights.is_empty() => ModelMixture::uniform(models),
+        Mix::Random => ModelMixture::new(models, &args.weights),
+    }.unwrap_or_else(|e| fail(e));
+
+    let case_sensitive = mixture.models.iter().any(|model| model.alphabet.case_sensitive);
+    let filters = name_filters(cli, cleaned_names, case_sensitive);
+    let accept = |name: &str| filters.iter().all(|filter| filter(name));
+    let options = cli.sample_options();
+    let mut rng = cli.rng();
+    let training: HashSet<&str> = cleaned_names.iter().map(|name| name.trim_matches('.')).collect();
+    let mode = cli.boundary_mode();
+    let mut rows: Vec<GeneratedName> = Vec::with_capacity(if args.stream { 0 } else { cli.count });
+    let progress = Progress::new(cli.progress && !args.quiet && !args.stream, Some(cli.count), "generating");
+    let mut stdout = std::io::stdout().lock();
+    let last = if args.stream { usize::MAX } else { cli.count };
+    let (mut index, mut closed) = (0, false);
+    let drawn = mixture.draw_names(last, &mut rng, &options, accept, |model, name| {
+        progress.inc(1);
+        index += 1;
+        let row = GeneratedName {
+            index,
+            nll_per_char: cli.score(&name, &mixture.models[model]).nll_per_char_log10,
+            novel: !training.contains(name.as_str()),
+            name,
+            model: args.load[model].display().to_string(),
+        };
+        if !args.stream {
+            rows.push(row);
+            return ControlFlow::Continue(());
+        }
+        // flushed every line for pipes, a closed pipe like the end of `head` ends the stream quietly
+        if write_generated(&mut stdout, args, &row).and_then(|_| stdout.flush()).is_err() {
+            closed = true;
+            return ControlFlow::Break(());
+        }
+        if let Some(delay) = args.delay_ms {
+            thread::sleep(Duration::from_millis(delay));
         }
+        ControlFlow::Continue(())
+    });
+    progress.finish();
+    if drawn < last && !closed {
+        match args.stream {
+            true => eprintln!("warning: stopped after {} names, no name passed the filters after {} attempts", drawn, MAX_ATTEMPTS),
+            false => warn_short_batch(cli, rows.len(), cli.count),
+        }
+    }
+    if args.stream {
+        return;
+    }
+
+    if args.table && !args.quiet && args.format == Format::Text {
+        print_generated_table(cli, &rows, mode);
+    } else {
+        for row in &rows {
+            write_generated(&mut stdout, args, row).unwrap_or_else(|e| fail(e));
+        }
+    }
+    if !args.quiet && args.format == Format::Text {
+        let names: Vec<String> = rows.into_iter().map(|row| row.name).collect();
+        print_length_check(cli, &names, cleaned_names);
     }
-    bigram_counts
 }
 
 /**
-    * Function to create the bigram matrix
-    * 1. Create a matrix of zeros
-    * 2. For each character starting and ending with the dot character, create a row for each bigram starting with that character
-    * 3. For each bigram, calculate the probability of that bigram by dividing the count by the total number of bigrams starting with that character
+    * Function to write one generated name the way the generate options ask for, outside of a table
 **/
-fn create_bigram_matrix(names: &Vec<String>, smoothing: f64) -> Vec<Vec<f64>> {
-    let mut bigram_matrix = vec![vec![smoothing; 27]; 27];
-    let mut bigram_totals = vec![smoothing as i64; 27];
-    // have to reset first index to 0.0; we never want a dot-dot bigram to be generated
-    bigram_totals[0] = 0;
-    bigram_matrix[0][0] = 0.0;
-    for name in &names[..] {
-        for i in 0..name.len() - 1 {
-            let bigram = &name[i..i+2];
-            // need to check for dot character
-            let mut first = 0;
-            let mut second = 0;
-            let chars = bigram.chars().collect::<Vec<char>>();
-            if chars[0] != '.' {
-                first = chars[0] as usize - 96;
+fn write_generated(out: &mut impl Write, args: &GenerateArgs, row: &GeneratedName) -> std::io::Result<()> {
+    if args.quiet {
+        writeln!(out, "{}", row.name)
+    } else if args.format == Format::Json {
+        writeln!(out, "{}", serde_json::to_string(row).expect("a row always serializes"))
+    } else {
+        writeln!(out, "{} (from {})", row.name, row.model)
+    }
+}
+
+/**
+    * Function to warn that fewer names were generated than asked for
+    * With --unique a small model may simply have run out of distinct names
+**/
+fn warn_short_batch(cli: &Cli, generated: usize, wanted: usize) {
+    if cli.unique {
+        eprintln!("warning: only {} distinct names of the {} asked for were found, after {} attempts each", generated, wanted, MAX_ATTEMPTS);
+    } else {
+        eprintln!("warning: no generated name passed the filters after {} attempts", MAX_ATTEMPTS);
+    }
+}
+
+/**
+    * Function to print generated names as an aligned table
+**/
+fn print_generated_table(cli: &Cli, rows: &[GeneratedName], mode: BoundaryMode) {
+    println!("scores are -log10(likelihood) per scored bigram, {}", mode_description(mode));
+    let width = rows.iter().map(|row| row.name.len()).max().unwrap_or(0).max("name".len());
+    println!("{:>4}  {:<width$}  {:>12}  {:<5}  model", "#", "name", "nll/char", "novel", width = width);
+    for row in rows {
+        println!("{:>4}  {:<width$}  {:>12}  {:<5}  {}", row.index, row.name, cli.decimals(row.nll_per_char), if row.novel { "yes" } else { "no" }, row.model, width = width);
+    }
+}
+
+/**
+    * Function to serve requests on a port, one connection at a time
+**/
+fn serve(cli: &Cli, model: &BigramModel, cleaned_names: &[String], port: u16) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|e| fail(format!("could not listen on port {}: {}", port, e)));
+    eprintln!("listening on 127.0.0.1:{}", port);
+    let filters = name_filters(cli, cleaned_names, model.alphabet.case_sensitive);
+    let accept = |name: &str| filters.iter().all(|filter| filter(name));
+    let mut rng = cli.rng();
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| handle_connection(cli, model, stream, &mut rng, accept));
+        // a client going away shouldn't stop the server
+        if let Err(e) = result {
+            eprintln!("connection failed: {}", e);
+        }
+    }
+}
+
+/**
+    * Function to answer the requests of one client until it quits or disconnects
+    * Every reply is newline delimited, a name or a score per line and errors start with `error:`
+**/
+fn handle_connection(cli: &Cli, model: &BigramModel, stream: TcpStream, rng: &mut StdRng, accept: impl Fn(&str) -> bool + Copy) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let options = cli.sample_options();
+    for line in BufReader::new(stream).lines() {
+        match parse_request(&line?) {
+            Ok(Request::Generate(count)) => {
+                for _ in 0..count {
+                    match model.sample_matching(rng, &options, accept) {
+                        Some(name) => writeln!(writer, "{}", name)?,
+                        None => {
+                            writeln!(writer, "error: no generated name passed the filters after {} attempts", MAX_ATTEMPTS)?;
+                            break;
+                        }
+                    }
+                }
             }
-            if chars[1] != '.' {
-                second = chars[1] as usize - 96;
+            Ok(Request::Score(word)) => {
+                writeln!(writer, "{}", cli.decimals(cli.score(&word, model).nll_per_char_log10))?;
             }
-            bigram_matrix[first][second] += 1.0;
-            bigram_totals[first] += 1;
+            Ok(Request::Quit) => break,
+            Err(message) => writeln!(writer, "error: {}", message)?,
         }
     }
-    for i in 0..27 {
-        for j in 0..27 {
-            bigram_matrix[i][j] /= bigram_totals[i] as f64;
+    Ok(())
+}
+
+/**
+    * Function to save the model to a temporary file, load it back and compare the two
+    * 1. Every probability of the loaded matrix has to be within SELFTEST_TOLERANCE of the original
+    * 2. Generating with the same seed has to give the same names from both models
+**/
+fn selftest(model: &BigramModel) {
+    let path = std::env::temp_dir().join(format!("bigram-selftest-{}.json", process::id()));
+    model.save(&path).unwrap_or_else(|e| fail(e));
+    let loaded = BigramModel::load(&path);
+    // clean up before checking so a failure doesn't leave the file behind
+    let _ = std::fs::remove_file(&path);
+    let loaded = loaded.unwrap_or_else(|e| fail(e));
+
+    let max_difference = model.matrix.iter().flatten()
+        .zip(loaded.matrix.iter().flatten())
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max);
+    if max_difference > SELFTEST_TOLERANCE {
+        fail(format!("selftest failed: loaded matrix differs by up to {}", max_difference));
+    }
+    println!("matrix round trip: ok (max difference {:e})", max_difference);
+
+    let generate = |model: &BigramModel| {
+        let mut rng = StdRng::seed_from_u64(SELFTEST_SEED);
+        (0..SELFTEST_NAMES).map(|_| model.sample_name(&mut rng)).collect::<Vec<String>>()
+    };
+    let (before, after) = (generate(model), generate(&loaded));
+    if let Some((a, b)) = before.iter().zip(&after).find(|(a, b)| a != b) {
+        fail(format!("selftest failed: seeded generation gave {:?} before and {:?} after the round trip", a, b));
+    }
+    println!("seeded generation: ok ({} names, seed {})", SELFTEST_NAMES, SELFTEST_SEED);
+    println!("selftest passed");
+}
+
+/**
+    * Function to parse a start bias like `m:3.0`
+**/
+fn parse_start_bias(value: &str) -> Result<(char, f64), String> {
+    let (c, weight) = value.split_once(':').ok_or("expected CHAR:WEIGHT")?;
+    let mut chars = c.chars();
+    let c = match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => c,
+        _ => return Err(format!("{:?} is not a single letter", c)),
+    };
+    let weight: f64 = weight.parse().map_err(|_| format!("{:?} is not a number", weight))?;
+    if !weight.is_finite() || weight < 0.0 {
+        return Err("the weight can't be negative".to_string());
+    }
+    Ok((c, weight))
+}
+
+/**
+    * Function to parse the probability floor of --epsilon
+**/
+fn parse_epsilon(value: &str) -> Result<f64, String> {
+    let epsilon: f64 = value.parse().map_err(|_| format!("{:?} is not a number", value))?;
+    if !(0.0..1.0).contains(&epsilon) {
+        return Err("the probability floor has to be at least 0 and below 1".to_string());
+    }
+    Ok(epsilon)
+}
+
+/**
+    * Function to parse the number of names --top-n-by-likelihood keeps
+    * Keeping none would silently print nothing
+**/
+fn parse_top_n(value: &str) -> Result<usize, String> {
+    let n: usize = value.parse().map_err(|_| format!("{:?} is not a count", value))?;
+    if n == 0 {
+        return Err("keep at least one name".to_string());
+    }
+    Ok(n)
+}
+
+/**
+    * Function to parse the held out share of eval --test-fraction
+    * None of the names would leave nothing to evaluate on, all of them nothing to train on
+**/
+fn parse_test_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value.parse().map_err(|_| format!("{:?} is not a number", value))?;
+    if !(fraction > 0.0 && fraction < 1.0) {
+        return Err("the test fraction has to be above 0 and below 1".to_string());
+    }
+    Ok(fraction)
+}
+
+/**
+    * Function to parse the interpolation weight of --lambda
+**/
+fn parse_lambda(value: &str) -> Result<f64, String> {
+    let lambda: f64 = value.parse().map_err(|_| format!("{:?} is not a number", value))?;
+    if !(0.0..=1.0).contains(&lambda) {
+        return Err("lambda has to be between 0 and 1".to_string());
+    }
+    Ok(lambda)
+}
+
+/**
+    * Function to parse the sampling temperature of --temperature
+**/
+fn parse_temperature(value: &str) -> Result<f64, String> {
+    let temperature: f64 = value.parse().map_err(|_| format!("{:?} is not a number", value))?;
+    if !temperature.is_finite() || temperature <= 0.0 {
+        return Err("the temperature has to be above 0".to_string());
+    }
+    Ok(temperature)
+}
+
+/**
+    * Function to check a --repeat-until expression, kept as text until the alphabet of the model is known
+**/
+fn predicate_expression(value: &str) -> Result<String, String> {
+    parse_predicate(value).map(|_| value.to_string())
+}
+
+/**
+    * Function to parse the weight of the boundary counts of --boundary-weight
+    * A weight of 0 would leave only the smoothing to end a name
+**/
+fn parse_boundary_weight(value: &str) -> Result<f64, String> {
+    let weight: f64 = value.parse().map_err(|_| format!("{:?} is not a number", value))?;
+    if !weight.is_finite() || weight <= 0.0 {
+        return Err("the boundary weight has to be above 0".to_string());
+    }
+    Ok(weight)
+}
+
+/**
+    * Function to parse the nucleus share of --top-p
+**/
+fn parse_top_p(value: &str) -> Result<f64, String> {
+    let p: f64 = value.parse().map_err(|_| format!("{:?} is not a number", value))?;
+    if !(p > 0.0 && p <= 1.0) {
+        return Err("top-p has to be above 0 and at most 1".to_string());
+    }
+    Ok(p)
+}
+
+/**
+    * Function to parse the probability floor of --min-step-prob
+**/
+fn parse_probability(value: &str) -> Result<f64, String> {
+    let p: f64 = value.parse().map_err(|_| format!("{:?} is not a number", value))?;
+    if !(0.0..1.0).contains(&p) {
+        return Err("the floor has to be at least 0 and below 1".to_string());
+    }
+    Ok(p)
+}
+
+/**
+    * Function to parse the count of --confidence-k
+**/
+fn parse_confidence_k(value: &str) -> Result<f64, String> {
+    let k: f64 = value.parse().map_err(|_| format!("{:?} is not a number", value))?;
+    if !k.is_finite() || k <= 0.0 {
+        return Err("k has to be above 0".to_string());
+    }
+    Ok(k)
+}
+
+/**
+    * Function to parse a transition constraint like `q:x:0`
+**/
+fn parse_constraint(value: &str) -> Result<(char, char, f64), String> {
+    let parts: Vec<&str> = value.split(':').collect();
+    let [previous, next, multiplier] = parts[..] else {
+        return Err("expected PREV:NEXT:MULT".to_string());
+    };
+    let single = |part: &str| {
+        let mut chars = part.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(format!("{:?} is not a single character", part)),
         }
+    };
+    let multiplier: f64 = multiplier.parse().map_err(|_| format!("{:?} is not a number", multiplier))?;
+    if !multiplier.is_finite() || multiplier < 0.0 {
+        return Err("the multiplier can't be negative".to_string());
     }
-    bigram_matrix
+    Ok((single(previous)?, single(next)?, multiplier))
+}
+
+/**
+    * Function to parse boundary tokens, one shared character or a start and an end
+**/
+fn parse_boundary_tokens(value: &str) -> Result<Alphabet, String> {
+    let chars: Vec<char> = value.chars().collect();
+    let (start, end) = match chars[..] {
+        [token] => (token, token),
+        [start, end] => (start, end),
+        _ => return Err("expected one token or a start and an end token".to_string()),
+    };
+    Alphabet::new(start, end).map_err(|_| "a boundary token can't be a letter".to_string())
 }
 
 /**
-    * Function to take in a row of the porbablity matrix
-    * and sample it as a multinomial distribution to return
-    * the nindex of the next character
+    * Function to print an error and exit
 **/
-fn sample_next_char(probablities: &Vec<f64>) -> usize {
-    // weighted index dist
-    let dist = WeightedIndex::new(probablities).unwrap();
-    // TODO might want to used seeded rng
-    // https://rust-random.github.io/rand/rand_core/trait.SeedableRng.html
-    let mut rng = thread_rng();
-    dist.sample(&mut rng)
+fn fail(error: impl Display) -> ! {
+    eprintln!("error: {}", error);
+    process::exit(1);
 }
 
 /**
-    * Function to convert an int to a char
+    * Function to run the default demo, scoring a few training names and generating new ones
 **/
-fn int_to_char(index: usize) -> char {
-    if index == 0 {
-        return '.';
+fn demo(cli: &Cli, model: &BigramModel, buckets: Option<&LengthBuckets>, cleaned_names: &[String]) {
+    if let (Some(top_n), Some(candidates)) = (cli.top_n_by_likelihood, cli.candidates) {
+        if candidates < top_n {
+            fail(format!("--candidates ({}) must be at least --top-n-by-likelihood ({})", candidates, top_n));
+        }
+    }
+    println!(" Welcome to the bigram name model!");
+    let mode = cli.boundary_mode();
+    println!("scores are -log10(likelihood) per scored bigram, {}", mode_description(mode));
+    // print the first few names and the neg log likelihood
+    for name in cleaned_names.iter().take(5) {
+        println!("name: {}, -log(likelihood): {}", model.alphabet.display(name), cli.decimals(cli.score(name, model).nll_per_char_log10));
     }
-    (index as u8 + 96) as char
+
+    // Sample the matrix a few times
+    let mut rng = cli.rng();
+    let mut sample = |count: usize| sample_batch(cli, model, buckets, cleaned_names, count, &mut rng);
+    let (wanted, generated) = match cli.top_n_by_likelihood {
+        // over-sample and keep the most likely names
+        Some(top_n) => {
+            let candidates = cli.candidates.unwrap_or(top_n * 10);
+            let mut generated = sample(candidates);
+            generated.sort_by(|a, b| a.1.total_cmp(&b.1));
+            generated.truncate(top_n);
+            (top_n, generated)
+        }
+        None => (cli.count, sample(cli.count)),
+    };
+    if generated.len() < wanted {
+        warn_short_batch(cli, generated.len(), wanted);
+    }
+    for (name, nll) in &generated {
+        println!("Generated name: {}, -log(likelihood): {}", model.alphabet.display(name), cli.decimals(nll / LN_10));
+    }
+    let names: Vec<String> = generated.into_iter().map(|(name, _)| name).collect();
+    print_length_check(cli, &names, cleaned_names);
 }
 
 /**
-    * Cacluate the likelihood of a word from the bigram matrix
+    * Function to print how far the lengths of generated names are from the training names, as a KS statistic
 **/
-fn likelihood_of_word(word: &str, bigram_matrix: &Vec<Vec<f64>>) -> f64 {
-    let mut likelihood = 1.0;
-    for i in 0..word.len() - 1 {
-        let bigram = &word[i..i+2];
-        let mut first = 0;
-        let mut second = 0;
-        let chars = bigram.chars().collect::<Vec<char>>();
-        if chars[0] != '.' {
-            first = chars[0] as usize - 96;
+fn print_length_check(cli: &Cli, generated: &[String], cleaned_names: &[String]) {
+    let Some(statistic) = length_ks_statistic(generated, cleaned_names) else {
+        return;
+    };
+    let critical = ks_critical_value(generated.len(), cleaned_names.len());
+    let verdict = if statistic > critical { "the lengths differ from the training names" } else { "consistent with the training names" };
+    println!("length KS statistic: {} (5% critical value {}, {})", cli.decimals(statistic), cli.decimals(critical), verdict);
+}
+
+/**
+    * Function to sample a batch of names with the sampling options and filters of the command line
+    * Each name comes with its negative log likelihood per scored bigram, in nats
+    * When a --prefix or --suffix is never sampled the batch ends on the most likely name with both, like sample_with_affixes.
+    * With --length-buckets every name comes from the model of one of the buckets and is scored by it
+**/
+fn sample_batch(cli: &Cli, model: &BigramModel, buckets: Option<&LengthBuckets>, cleaned_names: &[String], count: usize, rng: &mut StdRng) -> Vec<(String, f64)> {
+    let filters = name_filters(cli, cleaned_names, model.alphabet.case_sensitive);
+    let accept = |name: &str| filters.iter().all(|filter| filter(name));
+    let options = cli.sample_options();
+    // searched up front so letters outside of the alphabet are caught before sampling
+    let fallback = (cli.prefix.is_some() || cli.suffix.is_some()).then(|| {
+        model.most_likely_with_affixes(&options.prefix, cli.suffix.as_deref().unwrap_or_default(), AFFIX_SEARCH_LEN).unwrap_or_else(|e| fail(e))
+    }).flatten();
+    let mode = cli.boundary_mode();
+    let progress = Progress::new(cli.progress, Some(count), "generating");
+    let mut batch = match buckets {
+        Some(buckets) => buckets.generate_scored(count, rng, &options, mode, accept, |_| progress.inc(1)),
+        None => model.generate_scored(count, rng, &options, mode, accept, |_| progress.inc(1)),
+    };
+    progress.finish();
+    if batch.len() < count {
+        let repeated = |name: &str| options.unique && batch.iter().any(|(other, _)| other == name);
+        if let Some((name, _)) = fallback.filter(|(name, _)| accept(name) && !repeated(name)) {
+            eprintln!("warning: no sampled name matched the --prefix and --suffix, falling back to the most likely one");
+            let nll = score_word_with(&name, &model.matrix, mode).nll_per_char;
+            batch.push((name, nll));
         }
-        if chars[1] != '.' {
-            second = chars[1] as usize - 96;
+    }
+    batch
+}
+
+/**
+    * Function to describe which transitions a reported score includes
+**/
+fn mode_description(mode: BoundaryMode) -> &'static str {
+    match mode {
+        BoundaryMode::Inclusive => "including the start and end transitions",
+        BoundaryMode::Interior => "interior transitions only",
+    }
+}
+
+/**
+    * Function to print the statistics of a trained model
+**/
+fn print_model_stats(model: &BigramModel) {
+    let bigrams: f64 = model.counts.iter().flatten().sum();
+    let distinct = model.counts.iter().flatten().filter(|count| **count > 0.0).count();
+    println!("bigrams counted: {}", bigrams);
+    println!("distinct bigrams: {}", distinct);
+    println!("smoothing: {}", model.smoothing);
+    println!("expected name length: {:.4}", expected_name_length(&model.matrix));
+    println!("model hash: {:016x}", model.model_hash());
+}
+
+/**
+    * Function to write a Markdown report of the model stats, the most common bigrams, the entropy of the rows
+    * and a few generated names with their scores
+**/
+fn write_report(cli: &Cli, model: &BigramModel, buckets: Option<&LengthBuckets>, cleaned_names: &[String], path: &Path) {
+    let alphabet = &model.alphabet;
+    let mut lines = vec!["# Bigram model report".to_string(), String::new(), "## Model".to_string(), String::new()];
+    lines.push(format!("- training names: {}", cleaned_names.len()));
+    lines.push(format!("- bigrams counted: {}", model.counts.iter().flatten().sum::<f64>()));
+    lines.push(format!("- distinct bigrams: {}", model.counts.iter().flatten().filter(|count| **count > 0.0).count()));
+    lines.push(format!("- smoothing: {} ({:?})", model.smoothing, cli.smoothing_method()));
+    lines.push(format!("- alphabet: {} characters", model.size()));
+    lines.push(format!("- expected name length: {:.4}", expected_name_length(&model.matrix)));
+    lines.push(format!("- model hash: `{:016x}`", model.model_hash()));
+
+    lines.extend([String::new(), format!("## {} most common bigrams", REPORT_TOP_K), String::new()]);
+    lines.push("| bigram | count |".to_string());
+    lines.push("| --- | ---: |".to_string());
+    for (a, b, count) in model.most_common_bigrams(REPORT_TOP_K) {
+        lines.push(format!("| `{}{}` | {} |", a, b, count));
+    }
+
+    // the end row of a split alphabet is never left, so it has no entropy to report
+    let entropies: Vec<(char, f64)> = model.row_entropies().into_iter().enumerate()
+        .filter(|(i, _)| !alphabet.has_end_index() || *i != alphabet.end_index())
+        .map(|(i, entropy)| (alphabet.char_at(i), entropy))
+        .collect();
+    let mean = entropies.iter().map(|(_, entropy)| entropy).sum::<f64>() / entropies.len() as f64;
+    let lowest = entropies.iter().min_by(|a, b| a.1.total_cmp(&b.1)).expect("a model has rows");
+    let highest = entropies.iter().max_by(|a, b| a.1.total_cmp(&b.1)).expect("a model has rows");
+    lines.extend([String::new(), "## Entropy of the next character".to_string(), String::new()]);
+    lines.push(format!("- mean: {:.4} bits", mean));
+    lines.push(format!("- lowest: `{}` with {:.4} bits", lowest.0, lowest.1));
+    lines.push(format!("- highest: `{}` with {:.4} bits", highest.0, highest.1));
+
+    let mode = cli.boundary_mode();
+    lines.extend([String::new(), "## Generated names".to_string(), String::new()]);
+    lines.push(format!("Scores are -log10(likelihood) per scored bigram, {}.", mode_description(mode)));
+    lines.push(String::new());
+    lines.push("| name | score |".to_string());
+    lines.push("| --- | ---: |".to_string());
+    let mut rng = cli.rng();
+    for (name, nll) in sample_batch(cli, model, buckets, cleaned_names, REPORT_SAMPLE, &mut rng) {
+        lines.push(format!("| {} | {} |", name, cli.decimals(nll / LN_10)));
+    }
+
+    std::fs::write(path, lines.join("\n") + "\n")
+        .unwrap_or_else(|e| fail(format!("could not write the report to {}: {}", path.display(), e)));
+    println!("wrote report to {}", path.display());
+}
+
+/**
+    * Function to print the stationary distribution of the chain next to the letter frequencies of the training names
+    * A large distance means generation drifts towards letters the corpus uses less
+**/
+fn print_stationary(cli: &Cli, model: &BigramModel) {
+    let stationary = stationary_distribution(&model.matrix);
+    let inner: Vec<usize> = model.alphabet.letters().chain(model.alphabet.space_index()).collect();
+    // how often each character comes second in a training bigram
+    let seen: Vec<f64> = inner.iter().map(|j| model.counts.iter().map(|row| row[*j]).sum()).collect();
+    let total: f64 = seen.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+    println!("{:<4} {:>10} {:>10}", "char", "stationary", "training");
+    let mut distance = 0.0;
+    for (j, count) in inner.iter().zip(&seen) {
+        let empirical = count / total;
+        distance += (stationary[*j] - empirical).abs() / 2.0;
+        println!("{:<4} {:>10} {:>10}", format!("{:?}", model.alphabet.char_at(*j)), cli.decimals(stationary[*j]), cli.decimals(empirical));
+    }
+    println!("total variation distance: {}", cli.decimals(distance));
+}
+
+/**
+    * Function to print the letters with little inbound probability and the letters never starting a name
+**/
+fn print_coverage(cli: &Cli, model: &BigramModel, threshold: f64) {
+    let dead = model.dead_characters(threshold);
+    println!("letters with inbound probability below {}: {}", threshold, dead.len());
+    for (c, mass) in dead {
+        println!("  {} {}", c, cli.decimals(mass));
+    }
+    let never_starting = model.never_starting();
+    println!("letters never starting a name: {}", never_starting.len());
+    if !never_starting.is_empty() {
+        println!("  {}", never_starting.iter().collect::<String>());
+    }
+}
+
+/**
+    * Function to print the raw counts as a grid, or write them to a CSV file with the smoothed probabilities
+    * The grid has a row for each previous character and a column for each next character
+**/
+fn dump_counts(model: &BigramModel, csv: Option<&Path>) {
+    let alphabet = &model.alphabet;
+    let Some(path) = csv else {
+        let width = model.counts.iter().flatten().map(|count| count.to_string().len()).max().unwrap_or(1).max(1);
+        let header: Vec<String> = (0..model.size()).map(|j| format!("{:>width$}", alphabet.char_at(j), width = width)).collect();
+        println!("  {}", header.join(" "));
+        for (i, row) in model.counts.iter().enumerate() {
+            let cells: Vec<String> = row.iter().map(|count| format!("{:>width$}", count, width = width)).collect();
+            println!("{} {}", alphabet.char_at(i), cells.join(" "));
+        }
+        return;
+    };
+    let mut lines = vec!["previous,next,count,probability".to_string()];
+    for (i, row) in model.counts.iter().enumerate() {
+        for (j, count) in row.iter().enumerate() {
+            lines.push(format!("{},{},{},{}", alphabet.char_at(i), alphabet.char_at(j), count, model.matrix[i][j]));
         }
-        likelihood *= bigram_matrix[first][second];
     }
-    likelihood
+    std::fs::write(path, lines.join("\n") + "\n")
+        .unwrap_or_else(|e| fail(format!("could not write counts to {}: {}", path.display(), e)));
+    println!("wrote {} transitions to {}", lines.len() - 1, path.display());
 }
 
+/**
+    * Function to write the probability matrix as a LaTeX tabular, laid out like the counts grid
+    * Rows are the previous character and columns the next, with --latex-color every cell is shaded
+    * by its probability relative to the largest one
+**/
+fn export_latex(cli: &Cli, model: &BigramModel, path: &Path) {
+    let alphabet = &model.alphabet;
+    let max = model.matrix.iter().flatten().cloned().fold(0.0, f64::max);
+    let mut lines = vec!["% bigram probabilities, rows are the previous character and columns the next".to_string()];
+    if cli.latex_color {
+        lines.push("% \\cellcolor needs \\usepackage[table]{xcolor}".to_string());
+    }
+    lines.push(format!("\\begin{{tabular}}{{c|{}}}", "r".repeat(model.size())));
+    let header: Vec<String> = (0..model.size()).map(|j| latex_escape(alphabet.char_at(j))).collect();
+    lines.push(format!(" & {} \\\\", header.join(" & ")));
+    lines.push("\\hline".to_string());
+    for (i, row) in model.matrix.iter().enumerate() {
+        let cells: Vec<String> = row.iter()
+            .map(|p| match cli.latex_color && max > 0.0 {
+                true => format!("\\cellcolor{{blue!{}}}{}", (100.0 * p / max).round(), cli.decimals(*p)),
+                false => cli.decimals(*p),
+            })
+            .collect();
+        lines.push(format!("{} & {} \\\\", latex_escape(alphabet.char_at(i)), cells.join(" & ")));
+    }
+    lines.push("\\end{tabular}".to_string());
+    std::fs::write(path, lines.join("\n") + "\n")
+        .unwrap_or_else(|e| fail(format!("could not write the LaTeX table to {}: {}", path.display(), e)));
+    println!("wrote a {}x{} LaTeX table to {}", model.size(), model.size(), path.display());
+}
 
+/**
+    * Function to write a character of the alphabet so LaTeX prints it as itself
+**/
+fn latex_escape(c: char) -> String {
+    match c {
+        '#' | '$' | '%' | '&' | '_' | '{' | '}' => format!("\\{}", c),
+        '^' => "\\^{}".to_string(),
+        '~' => "\\textasciitilde{}".to_string(),
+        '\\' => "\\textbackslash{}".to_string(),
+        ' ' => "\\textvisiblespace{}".to_string(),
+        c => c.to_string(),
+    }
+}
+
+/**
+    * Function to print a sample of cleaned corpus lines and counts of the lines cleaning changes
+**/
+fn preview(corpus: &Path, sample: usize, gzip: bool, options: &CleanOptions) {
+    let lines = read_corpus_with(corpus, gzip).unwrap_or_else(|e| fail(e));
+    let mut seen = HashSet::new();
+    let (mut empty, mut non_ascii, mut duplicates) = (0, 0, 0);
+    for (i, line) in lines.iter().enumerate() {
+        let cleaned = clean_name_with(line, options);
+        if i < sample {
+            println!("{:?} -> {:?}", line, cleaned);
+        }
+        if !line.is_ascii() {
+            non_ascii += 1;
+        }
+        if cleaned == ".." {
+            empty += 1;
+        } else if !seen.insert(cleaned) {
+            duplicates += 1;
+        }
+    }
+    println!("total lines: {}", lines.len());
+    println!("empty after cleaning: {}", empty);
+    println!("containing non-ASCII: {}", non_ascii);
+    println!("duplicates after cleaning: {}", duplicates);
+}
+
+/**
+    * Function to print the score of each word, optionally with every transition behind it
+**/
+fn print_scores(cli: &Cli, model: &BigramModel, words: &[String], verbose: bool) {
+    let mode = cli.boundary_mode();
+    println!("scores are -log10(likelihood) per scored bigram, {}", mode_description(mode));
+    for word in words {
+        println!("{}: {}", word, cli.decimals(cli.score(word, model).nll_per_char_log10));
+        let unknown = model.out_of_vocabulary(word);
+        if !unknown.is_empty() {
+            let unknown: Vec<String> = unknown.iter().map(|c| format!("'{}'", c)).collect();
+            println!("  out of vocabulary: {}", unknown.join(", "));
+        }
+        if verbose {
+            let mut log_probability = 0.0;
+            let (sensitivity, derivatives) = model.smoothing_sensitivity(word, mode);
+            for ((previous, next, p), (_, _, derivative)) in model.transition_breakdown(word, mode).into_iter().zip(derivatives) {
+                log_probability += p.max(cli.epsilon.unwrap_or(0.0)).ln();
+                println!("  ({}, {}) -> {}  ln p = {}  d(-ln p)/dk = {}", previous, next, cli.decimals(p), cli.decimals(log_probability), cli.decimals(derivative));
+            }
+            println!("  d(-ln p per scored bigram)/dk at k = {}: {}", model.smoothing, cli.decimals(sensitivity));
+        }
+    }
+}
+
+/**
+    * Function to print the k most and least associated bigrams
+**/
+fn print_pmi(cli: &Cli, model: &BigramModel, k: usize) {
+    let (highest, lowest) = model.pmi_extremes(k);
+    println!("pmi is ln(P(a, b) / (P(a) P(b))) over the bigrams seen in training");
+    println!("most associated bigrams:");
+    for (a, b, pmi) in highest {
+        println!("  {}{} {}", a, b, cli.decimals(pmi));
+    }
+    println!("least associated bigrams:");
+    for (a, b, pmi) in lowest {
+        println!("  {}{} {}", a, b, cli.decimals(pmi));
+    }
+}
+
+/**
+    * Function to print the clusters of near-duplicate training names, with how often each name was seen
+**/
+fn print_duplicates(cleaned_names: &[String], max_edit: usize) {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for name in cleaned_names {
+        *counts.entry(name.trim_matches('.')).or_insert(0) += 1;
+    }
+    let clusters = near_duplicate_clusters(cleaned_names, max_edit);
+    let mut stdout = std::io::stdout().lock();
+    let written = writeln!(stdout, "{} clusters of names within {} edits of each other", clusters.len(), max_edit).and_then(|_| {
+        for cluster in &clusters {
+            let names: Vec<String> = cluster.iter().map(|name| format!("{} ({})", name, counts[name.as_str()])).collect();
+            writeln!(stdout, "  {}", names.join(", "))?;
+        }
+        stdout.flush()
+    });
+    // a closed pipe like the end of `head` stops the list quietly, like generate --stream
+    if let Err(e) = written {
+        if e.kind() != ErrorKind::BrokenPipe {
+            fail(e);
+        }
+    }
+}
+
+/**
+    * Function to print the top and bottom k training names by negative log likelihood per character
+    * The least likely names tend to show noise in the corpus, like typos or foreign spellings
+**/
+fn print_extremes(cli: &Cli, model: &BigramModel, cleaned_names: &[String], k: usize) {
+    let mode = cli.boundary_mode();
+    let mut unique: Vec<&String> = cleaned_names.iter().collect();
+    unique.sort();
+    unique.dedup();
+    let progress = Progress::new(cli.progress, Some(unique.len()), "scoring");
+    let mut scored: Vec<(&str, f64)> = unique.iter()
+        .map(|name| {
+            progress.inc(1);
+            (name.trim_matches('.'), cli.score(name, model).nll_per_char)
+        })
+        .collect();
+    progress.finish();
+    scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    println!("scores are -ln(likelihood) per scored bigram, {}", mode_description(mode));
+    println!("most likely training names:");
+    for (name, nll) in scored.iter().take(k) {
+        println!("  {:<15} {}", name, cli.decimals(*nll));
+    }
+    println!("least likely training names:");
+    for (name, nll) in scored.iter().rev().take(k) {
+        println!("  {:<15} {}", name, cli.decimals(*nll));
+    }
+}
+
+/**
+    * Function to print which training names hold each bigram of a cleaned name
+    * A bigram no name holds is only possible through smoothing, so the cover can't include it
+    * 1. List every bigram with how many distinct training names hold it and up to k of them
+    * 2. Print a small set of names that together hold every bigram the corpus has
+**/
+fn explain(model: &BigramModel, cleaned_names: &[String], target: &str, k: usize) {
+    let sources = bigram_sources(target, cleaned_names, &model.alphabet);
+    println!("bigrams of {}:", model.alphabet.display(target));
+    for source in &sources {
+        let bigram = format!("{}{}", source.previous, source.next);
+        match source.names.len() {
+            0 => println!("  {}  in no training name, only smoothing makes it possible", bigram),
+            held => {
+                let examples: Vec<&str> = source.names.iter().take(k).map(String::as_str).collect();
+                println!("  {}  in {} names, e.g. {}", bigram, held, examples.join(", "));
+            }
+        }
+    }
+    let cover = covering_names(&sources);
+    println!("every seen bigram is held by these {} training names: {}", cover.len(), cover.join(", "));
+}
+
+/**
+    * Function to write the heatmap of the model, exiting on failure
+**/
+#[cfg(feature = "png")]
+fn export_png(model: &BigramModel, path: &std::path::Path) {
+    if let Err(e) = bigram::heatmap::export_png(model, path) {
+        fail(format!("could not write heatmap to {}: {}", path.display(), e));
+    }
+    println!("Wrote heatmap to {}", path.display());
+}
+
+#[cfg(not(feature = "png"))]
+fn export_png(_model: &BigramModel, _path: &std::path::Path) {
+    fail("--export-png needs the png feature, rebuild with `--features png`");
+}
+
+/**
+    * Function to collect the filters generated names have to pass from the options
+**/
+fn name_filters(cli: &Cli, training_names: &[String], case_sensitive: bool) -> Vec<NameFilter> {
+    let mut filters: Vec<NameFilter> = Vec::new();
+    if let Some(pattern) = &cli.pattern {
+        filters.push(regex_filter(pattern));
+    }
+    if let Some(expression) = &cli.repeat_until {
+        // parsed again now the alphabet is known, text stays cased for a case sensitive model
+        let predicate = parse_predicate_in(expression, case_sensitive).unwrap_or_else(|e| fail(e));
+        filters.push(Box::new(move |name| predicate.matches(name)));
+    }
+    if let Some(suffix) = &cli.suffix {
+        let suffix = suffix.clone();
+        filters.push(Box::new(move |name| name.ends_with(&suffix)));
+    }
+    if let Some(min_distance) = cli.min_edit_distance.filter(|d| *d > 0) {
+        let index = NameIndex::new(training_names);
+        filters.push(Box::new(move |name| !index.has_name_within(name, min_distance - 1)));
+    }
+    filters
+}
+
+/**
+    * Function to compile the --match pattern into a filter, exiting if it is invalid
+**/
+#[cfg(feature = "regex")]
+fn regex_filter(pattern: &str) -> NameFilter {
+    match regex::Regex::new(pattern) {
+        Ok(re) => Box::new(move |name| re.is_match(name)),
+        Err(e) => fail(format!("invalid --match pattern: {}", e)),
+    }
+}
+
+#[cfg(not(feature = "regex"))]
+fn regex_filter(_pattern: &str) -> NameFilter {
+    fail("--match needs the regex feature, rebuild with `--features regex`");
+}
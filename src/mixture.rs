@@ -0,0 +1,132 @@
+/*!
+    * Generating a batch of names from several models, picking one model per name
+ **/
+
+use std::ops::RangeInclusive;
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
+use rand::Rng;
+use crate::{BigramError, BigramModel, NameSource, SampleOptions};
+
+/**
+    * A set of models names are generated from, one randomly chosen model per name
+    * Unlike merging the matrices, each name only follows the transitions of one model
+**/
+pub struct ModelMixture {
+    pub models: Vec<BigramModel>,
+    chooser: WeightedIndex<f64>,
+}
+
+impl ModelMixture {
+    /**
+        * Function to make a mixture where each model is picked in proportion to its weight
+        * Fails without models, with a weight per model missing, or if no weight is positive
+    **/
+    pub fn new(models: Vec<BigramModel>, weights: &[f64]) -> Result<ModelMixture, BigramError> {
+        if models.is_empty() {
+            return Err(BigramError::InvalidMixture("there are no models to mix".to_string()));
+        }
+        if weights.len() != models.len() {
+            return Err(BigramError::InvalidMixture(format!("{} weights given for {} models", weights.len(), models.len())));
+        }
+        let chooser = WeightedIndex::new(weights).map_err(|e| BigramError::InvalidMixture(e.to_string()))?;
+        Ok(ModelMixture { models, chooser })
+    }
+
+    /**
+        * Function to make a mixture picking every model equally often
+    **/
+    pub fn uniform(models: Vec<BigramModel>) -> Result<ModelMixture, BigramError> {
+        let weights = vec![1.0; models.len()];
+        ModelMixture::new(models, &weights)
+    }
+
+    /**
+        * Function to pick a model and sample a name from it that passes the filter
+        * Returns the index of the model with the name, or None like sample_matching
+    **/
+    pub fn sample_matching(&self, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool) -> Option<(usize, String)> {
+        let index = self.choose(rng);
+        self.models[index].sample_matching(rng, options, accept).map(|name| (index, name))
+    }
+
+    fn choose(&self, rng: &mut impl Rng) -> usize {
+        self.chooser.sample(rng)
+    }
+}
+
+impl NameSource for ModelMixture {
+    fn sample_source(&self, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool) -> Option<(usize, String)> {
+        self.sample_matching(rng, options, accept)
+    }
+
+    fn source_model(&self, index: usize) -> &BigramModel {
+        &self.models[index]
+    }
+}
+
+/**
+    * Models trained on the names of each range of lengths, a mixture of experts by length
+    * A bucket is picked in proportion to its share of the corpus and only names of its lengths are kept,
+    * so the lengths follow the corpus while each model only learns the transitions of names like its own
+**/
+pub struct LengthBuckets {
+    pub mixture: ModelMixture,
+    // the lengths of the names of each model, boundaries aside
+    pub ranges: Vec<RangeInclusive<usize>>,
+}
+
+impl LengthBuckets {
+    /**
+        * Function to split cleaned names at upper bounds of their length and train a model on every part
+        * Bounds of 3 and 6 give the buckets 1-3, 4-6 and 7 or more, a bucket no name falls in is left out
+        * Fails if the bounds aren't positive and increasing, if no bucket has names, or if training fails
+    **/
+    pub fn train(cleaned_names: &[String], bounds: &[usize], train: impl Fn(&[String]) -> Result<BigramModel, BigramError>) -> Result<LengthBuckets, BigramError> {
+        if bounds.first() == Some(&0) || bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(BigramError::InvalidMixture(format!("length bounds {:?} have to be positive and increasing", bounds)));
+        }
+        let starts = std::iter::once(1).chain(bounds.iter().map(|bound| bound + 1));
+        let ends = bounds.iter().cloned().chain(std::iter::once(usize::MAX));
+        let (mut models, mut ranges, mut weights) = (Vec::new(), Vec::new(), Vec::new());
+        for range in starts.zip(ends).map(|(start, end)| start..=end) {
+            let bucket: Vec<String> = cleaned_names.iter().filter(|name| range.contains(&name_length(name))).cloned().collect();
+            if bucket.is_empty() {
+                continue;
+            }
+            weights.push(bucket.len() as f64);
+            models.push(train(&bucket)?);
+            ranges.push(range);
+        }
+        Ok(LengthBuckets { mixture: ModelMixture::new(models, &weights)?, ranges })
+    }
+
+    /**
+        * Function to pick a bucket and sample a name of its lengths that passes the filter
+        * Returns the index of the bucket with the name, or None like sample_matching
+    **/
+    pub fn sample_matching(&self, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool) -> Option<(usize, String)> {
+        let index = self.mixture.choose(rng);
+        let range = &self.ranges[index];
+        self.mixture.models[index]
+            .sample_matching(rng, options, |name| range.contains(&name_length(name)) && accept(name))
+            .map(|name| (index, name))
+    }
+}
+
+impl NameSource for LengthBuckets {
+    fn sample_source(&self, rng: &mut impl Rng, options: &SampleOptions, accept: impl Fn(&str) -> bool) -> Option<(usize, String)> {
+        self.sample_matching(rng, options, accept)
+    }
+
+    fn source_model(&self, index: usize) -> &BigramModel {
+        &self.mixture.models[index]
+    }
+}
+
+/**
+    * Function to get the length of a name, without its boundary dots if it is cleaned
+**/
+fn name_length(name: &str) -> usize {
+    name.trim_matches('.').chars().count()
+}
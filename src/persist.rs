@@ -0,0 +1,169 @@
+/*!
+    * Saving and loading trained models as JSON, as a compact quantized matrix, or as a flat matrix for other languages
+ **/
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use crate::{Alphabet, BigramError, BigramModel};
+
+// the first bytes of a quantized model, so load can tell it from JSON
+const QUANTIZED_MAGIC: &[u8; 4] = b"BGQ8";
+// the largest quantized probability, given to the most likely transition of each row
+const QUANTIZED_MAX: f64 = u8::MAX as f64;
+// offset basis and prime of 64 bit FNV-1a, which model_hash uses
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+impl BigramModel {
+    /**
+        * Function to write the model to a JSON file
+        * The raw counts are saved next to the matrix so a loaded model can keep training
+    **/
+    pub fn save(&self, path: &Path) -> Result<(), BigramError> {
+        let file = File::create(path).map_err(|e| BigramError::Io(format!("{}: {}", path.display(), e)))?;
+        serde_json::to_writer(BufWriter::new(file), self)
+            .map_err(|e| BigramError::Io(format!("{}: {}", path.display(), e)))
+    }
+
+    /**
+        * Function to read a model written by save or save_quantized
+        * Fails if the file isn't a model or the matrices don't match the alphabet
+        * Models saved without an alphabet share the dot boundary
+    **/
+    pub fn load(path: &Path) -> Result<BigramModel, BigramError> {
+        let bytes = fs::read(path).map_err(|e| BigramError::Io(format!("{}: {}", path.display(), e)))?;
+        if bytes.starts_with(QUANTIZED_MAGIC) {
+            return BigramModel::from_quantized(&bytes).map_err(|message| BigramError::InvalidModel(format!("{}: {}", path.display(), message)));
+        }
+        let model: BigramModel = serde_json::from_slice(&bytes)
+            .map_err(|e| BigramError::InvalidModel(format!("{}: {}", path.display(), e)))?;
+        let size = model.alphabet.size();
+        let square = |matrix: &[Vec<f64>]| matrix.len() == size && matrix.iter().all(|row| row.len() == size);
+        if !square(&model.counts) || !square(&model.matrix) {
+            return Err(BigramError::InvalidModel(format!("{}: expected {}x{} matrices", path.display(), size, size)));
+        }
+        Ok(model)
+    }
+
+    /**
+        * Function to write the matrix quantized to one byte per probability
+        * The file is far smaller than the JSON one, but it keeps no counts and loses precision, see quantize
+    **/
+    pub fn save_quantized(&self, path: &Path) -> Result<(), BigramError> {
+        fs::write(path, self.quantize()).map_err(|e| BigramError::Io(format!("{}: {}", path.display(), e)))
+    }
+
+    /**
+        * Function to quantize the matrix to one byte per probability
+        * There is no scale in the file, every row is renormalized when it is read back.
+        * A third flag says the excluded letters follow as 8 more bytes, so files without any read as before
+        * 1. Write the magic bytes, the boundary tokens and whether the alphabet has upper case letters and spaces
+        * 2. Scale every row by its largest probability so that one becomes 255, and round the rest
+        * 3. Round a possible transition up to at least 1, so no name the model can make becomes impossible
+    **/
+    pub fn quantize(&self) -> Vec<u8> {
+        let mut bytes = QUANTIZED_MAGIC.to_vec();
+        for token in [self.alphabet.start, self.alphabet.end] {
+            bytes.extend((token as u32).to_le_bytes());
+        }
+        let excluded = self.alphabet.excluded != 0;
+        bytes.push(self.alphabet.case_sensitive as u8 | (self.alphabet.spaces as u8) << 1 | (excluded as u8) << 2);
+        if excluded {
+            bytes.extend(self.alphabet.excluded.to_le_bytes());
+        }
+        for row in &self.matrix {
+            let max = row.iter().cloned().fold(0.0, f64::max);
+            bytes.extend(row.iter().map(|p| match *p {
+                p if p > 0.0 => (p / max * QUANTIZED_MAX).round().max(1.0) as u8,
+                _ => 0,
+            }));
+        }
+        bytes
+    }
+
+    /**
+        * Function to read a matrix written by quantize, renormalizing every row
+        * Like from_matrix there are no counts behind it, so they are left as zeros
+        * Fails with the reason if the bytes are cut short or the tokens aren't characters
+    **/
+    pub fn from_quantized(bytes: &[u8]) -> Result<BigramModel, String> {
+        let mut header = QUANTIZED_MAGIC.len() + 9;
+        if bytes.len() < header || !bytes.starts_with(QUANTIZED_MAGIC) {
+            return Err("not a quantized model".to_string());
+        }
+        let token = |offset: usize| {
+            let code = u32::from_le_bytes(bytes[offset..offset + 4].try_into().expect("the header is long enough"));
+            char::from_u32(code).ok_or_else(|| format!("invalid boundary token {}", code))
+        };
+        let flags = bytes[header - 1];
+        let mut excluded = 0;
+        if flags & 4 != 0 {
+            let mask = bytes.get(header..header + 8).ok_or("the excluded letters are cut short")?;
+            excluded = u64::from_le_bytes(mask.try_into().expect("the mask is 8 bytes"));
+            header += 8;
+        }
+        let alphabet = Alphabet { start: token(4)?, end: token(8)?, case_sensitive: flags & 1 != 0, spaces: flags & 2 != 0, excluded };
+        let size = alphabet.size();
+        let cells = &bytes[header..];
+        if cells.len() != size * size {
+            return Err(format!("expected {}x{} probabilities", size, size));
+        }
+        let matrix = cells.chunks(size)
+            .map(|row| {
+                let total: f64 = row.iter().map(|q| *q as f64).sum();
+                row.iter().map(|q| if total > 0.0 { *q as f64 / total } else { 0.0 }).collect()
+            })
+            .collect();
+        Ok(BigramModel { alphabet, ..BigramModel::from_matrix(matrix) })
+    }
+
+    /**
+        * Function to flatten the matrix into one row-major vector, with the number of rows and columns
+        * The probability of moving from i to j is at i * size + j, which a C or Python caller can read without serde
+    **/
+    pub fn to_flat(&self) -> (Vec<f64>, usize) {
+        (self.matrix.concat(), self.size())
+    }
+
+    /**
+        * Function to rebuild a model from a matrix flattened by to_flat
+        * Like from_matrix there are no counts behind it, and the alphabet follows from the size.
+        * Fails with the reason if the data isn't size x size or no alphabet has that size
+    **/
+    pub fn from_flat(data: &[f64], alphabet_size: usize) -> Result<BigramModel, String> {
+        if data.len() != alphabet_size * alphabet_size {
+            return Err(format!("expected {}x{} = {} probabilities, got {}", alphabet_size, alphabet_size, alphabet_size * alphabet_size, data.len()));
+        }
+        if Alphabet::for_size(alphabet_size).size() != alphabet_size {
+            return Err(format!("no alphabet has {} characters", alphabet_size));
+        }
+        Ok(BigramModel::from_matrix(data.chunks(alphabet_size).map(|row| row.to_vec()).collect()))
+    }
+
+    /**
+        * Function to fingerprint the model for cache keys, the same alphabet and matrix always give the same hash
+        * 1. Feed the boundary tokens, the case and spaces flags and the size to 64 bit FNV-1a, in little endian
+        * 2. Feed the mask of excluded letters, only when some are, so models without any keep their old hash
+        * 3. Feed every probability row by row as the bits of its f64, with -0.0 read as 0.0
+        * FNV-1a is fixed by its constants, unlike the hasher of HashMap which is seeded per process.
+        * The counts and the smoothing aren't hashed, two models that generate and score alike share a hash
+    **/
+    pub fn model_hash(&self) -> u64 {
+        let mut bytes = Vec::with_capacity(21 + 8 * self.size() * self.size());
+        for token in [self.alphabet.start, self.alphabet.end] {
+            bytes.extend((token as u32).to_le_bytes());
+        }
+        bytes.push(self.alphabet.case_sensitive as u8 | (self.alphabet.spaces as u8) << 1);
+        bytes.extend((self.size() as u32).to_le_bytes());
+        // excluded letters keep their index, so the size alone doesn't tell the alphabets apart
+        if self.alphabet.excluded != 0 {
+            bytes.extend(self.alphabet.excluded.to_le_bytes());
+        }
+        for p in self.matrix.iter().flatten() {
+            // adding 0.0 turns -0.0 into 0.0 and leaves every other value alone
+            bytes.extend((p + 0.0).to_bits().to_le_bytes());
+        }
+        bytes.iter().fold(FNV_OFFSET, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME))
+    }
+}
@@ -0,0 +1,103 @@
+/*!
+    * A small predicate language for generated names, like `len>=4 && starts=m && !contains=xx`
+ **/
+
+/**
+    * A check on a generated name
+**/
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    // at least this many letters
+    MinLen(usize),
+    // at most this many letters
+    MaxLen(usize),
+    StartsWith(String),
+    EndsWith(String),
+    Contains(String),
+    Not(Box<Predicate>),
+    // every predicate holds
+    All(Vec<Predicate>),
+}
+
+impl Predicate {
+    /**
+        * Function to check a name, given without the boundary dots
+    **/
+    pub fn matches(&self, name: &str) -> bool {
+        let length = name.chars().count();
+        match self {
+            Predicate::MinLen(min) => length >= *min,
+            Predicate::MaxLen(max) => length <= *max,
+            Predicate::StartsWith(prefix) => name.starts_with(prefix.as_str()),
+            Predicate::EndsWith(suffix) => name.ends_with(suffix.as_str()),
+            Predicate::Contains(text) => name.contains(text.as_str()),
+            Predicate::Not(predicate) => !predicate.matches(name),
+            Predicate::All(predicates) => predicates.iter().all(|predicate| predicate.matches(name)),
+        }
+    }
+}
+
+/**
+    * Function to parse a predicate expression
+    * Terms are joined with `&&` and a term can be negated with a leading `!`
+    * 1. `len>=N`, `len<=N`, `len>N`, `len<N` and `len=N` compare the number of letters
+    * 2. `starts=TEXT`, `ends=TEXT` and `contains=TEXT` match text in the name
+    * 3. `not-contains=TEXT` is the same as `!contains=TEXT`
+**/
+pub fn parse_predicate(expression: &str) -> Result<Predicate, String> {
+    parse_predicate_in(expression, false)
+}
+
+/**
+    * Function to parse a predicate expression like parse_predicate, for names of a case sensitive alphabet or not
+    * Text is only lowercased when the alphabet isn't case sensitive, where names never hold capitals
+**/
+pub fn parse_predicate_in(expression: &str, case_sensitive: bool) -> Result<Predicate, String> {
+    let terms = expression.split("&&")
+        .map(|term| parse_term(term.trim(), case_sensitive))
+        .collect::<Result<Vec<Predicate>, String>>()?;
+    Ok(Predicate::All(terms))
+}
+
+/**
+    * Function to parse a single term of an expression
+**/
+fn parse_term(term: &str, case_sensitive: bool) -> Result<Predicate, String> {
+    if let Some(negated) = term.strip_prefix('!') {
+        return Ok(Predicate::Not(Box::new(parse_term(negated.trim(), case_sensitive)?)));
+    }
+    if let Some(comparison) = term.strip_prefix("len") {
+        return parse_length(comparison.trim());
+    }
+    let (key, value) = term.split_once('=').ok_or_else(|| format!("{:?} is not a predicate", term))?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(format!("{:?} needs text after the =", term));
+    }
+    let value = if case_sensitive { value.to_string() } else { value.to_lowercase() };
+    match key.trim() {
+        "starts" => Ok(Predicate::StartsWith(value)),
+        "ends" => Ok(Predicate::EndsWith(value)),
+        "contains" => Ok(Predicate::Contains(value)),
+        "not-contains" => Ok(Predicate::Not(Box::new(Predicate::Contains(value)))),
+        key => Err(format!("unknown predicate {:?}, expected len, starts, ends, contains or not-contains", key)),
+    }
+}
+
+/**
+    * Function to parse the comparison after `len`
+**/
+fn parse_length(comparison: &str) -> Result<Predicate, String> {
+    let (operator, number) = ["<=", ">=", "<", ">", "="].iter()
+        .find_map(|operator| comparison.strip_prefix(operator).map(|number| (*operator, number.trim())))
+        .ok_or_else(|| format!("expected a comparison after len, got {:?}", comparison))?;
+    let n: usize = number.parse().map_err(|_| format!("{:?} is not a length", number))?;
+    let predicate = match operator {
+        "<=" => Predicate::MaxLen(n),
+        ">=" => Predicate::MinLen(n),
+        "<" => Predicate::MaxLen(n.checked_sub(1).ok_or("len<0 can never hold")?),
+        ">" => Predicate::MinLen(n + 1),
+        _ => Predicate::All(vec![Predicate::MinLen(n), Predicate::MaxLen(n)]),
+    };
+    Ok(predicate)
+}
@@ -0,0 +1,49 @@
+/*!
+    * The line protocol of the name server, one request per line
+ **/
+
+// most names a single gen request may ask for
+pub const MAX_GEN_COUNT: usize = 1000;
+
+/**
+    * A request sent to the name server
+**/
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    // generate this many names, one per line
+    Generate(usize),
+    // score a word, one line
+    Score(String),
+    // close the connection
+    Quit,
+}
+
+/**
+    * Function to parse one request line
+    * Returns a message for the client if the line isn't a request
+    * 1. `gen` generates a single name and `gen N` generates N
+    * 2. `score WORD` scores a word
+    * 3. `quit` ends the connection
+**/
+pub fn parse_request(line: &str) -> Result<Request, String> {
+    let mut words = line.split_whitespace();
+    let request = match (words.next(), words.next()) {
+        (Some("gen"), None) => Request::Generate(1),
+        (Some("gen"), Some(count)) => {
+            let count: usize = count.parse().map_err(|_| format!("{:?} is not a number of names", count))?;
+            if count > MAX_GEN_COUNT {
+                return Err(format!("at most {} names can be generated at once", MAX_GEN_COUNT));
+            }
+            Request::Generate(count)
+        }
+        (Some("score"), Some(word)) => Request::Score(word.to_string()),
+        (Some("score"), None) => return Err("score needs a word".to_string()),
+        (Some("quit"), None) => Request::Quit,
+        (Some(command), _) => return Err(format!("unknown request {:?}, expected gen [N], score WORD or quit", command)),
+        (None, _) => return Err("empty request".to_string()),
+    };
+    if words.next().is_some() {
+        return Err("too many arguments".to_string());
+    }
+    Ok(request)
+}
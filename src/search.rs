@@ -0,0 +1,148 @@
+/*!
+    * Exact searches over the transition matrix
+ **/
+
+use crate::{BigramError, BigramModel};
+
+impl BigramModel {
+    /**
+        * Function to find the most probable name of at most max_len letters
+        * 1. For each length, track the best log probability of a path from the start ending in each letter
+        * 2. Close every path with the transition to the boundary and keep the best complete name
+        * 3. Follow the back pointers of the best complete name to rebuild it
+        * Returns the name and its natural log probability, or None if no name can end within max_len
+    **/
+    pub fn most_likely_name(&self, max_len: usize) -> Option<(String, f64)> {
+        let ln = |p: f64| p.ln();
+        let size = self.size();
+        let end = self.alphabet.end_index();
+        let letters = self.alphabet.letters();
+        // best[c] is the best log probability of the current length ending in letter c
+        let mut best: Vec<f64> = (0..size).map(|c| if letters.contains(&c) { ln(self.matrix[0][c]) } else { f64::NEG_INFINITY }).collect();
+        // back[t][c] is the letter before c on the best path of length t + 1
+        let mut back: Vec<Vec<usize>> = vec![vec![0; size]];
+        let mut winner: Option<(usize, usize, f64)> = None;
+        for length in 1..=max_len {
+            for c in letters.clone() {
+                let complete = best[c] + ln(self.matrix[c][end]);
+                if complete > f64::NEG_INFINITY && winner.is_none_or(|(_, _, top)| complete > top) {
+                    winner = Some((length, c, complete));
+                }
+            }
+            if length == max_len {
+                break;
+            }
+            let mut next = vec![f64::NEG_INFINITY; size];
+            let mut pointers = vec![0; size];
+            for c in letters.clone() {
+                for n in letters.clone() {
+                    let candidate = best[c] + ln(self.matrix[c][n]);
+                    if candidate > next[n] {
+                        next[n] = candidate;
+                        pointers[n] = c;
+                    }
+                }
+            }
+            best = next;
+            back.push(pointers);
+        }
+
+        let (length, last, score) = winner?;
+        let mut letters = vec![last];
+        for t in (1..length).rev() {
+            letters.push(back[t][letters[letters.len() - 1]]);
+        }
+        let name = letters.iter().rev().map(|c| self.alphabet.char_at(*c)).collect();
+        Some((name, score))
+    }
+
+    /**
+        * Function to find the most probable name of at most max_len letters that starts with a prefix and ends with a suffix
+        * 1. Score the names where the end of the prefix is already the start of the suffix, like "ma" for "ma" and "a"
+        * 2. Track the best path through each letter after the prefix for every length of the middle,
+        *    closed by the suffix and the end like most_likely_name
+        * 3. Follow the back pointers of the best middle to rebuild the name
+        * Returns None if no such name can be generated, and fails if either holds a character outside of the alphabet
+    **/
+    pub fn most_likely_with_affixes(&self, prefix: &str, suffix: &str, max_len: usize) -> Result<Option<(String, f64)>, BigramError> {
+        let (head, tail) = (self.affix_indices(prefix)?, self.affix_indices(suffix)?);
+        let ln = |p: f64| p.ln();
+        let end = self.alphabet.end_index();
+        // the log probability of walking a run of letters from a character, ending on the end boundary if asked
+        let walk = |from: usize, letters: &[usize], close: bool| -> f64 {
+            let mut previous = from;
+            let mut total = 0.0;
+            for letter in letters {
+                total += ln(self.matrix[previous][*letter]);
+                previous = *letter;
+            }
+            if close { total + ln(self.matrix[previous][end]) } else { total }
+        };
+
+        let mut winner: Option<(Vec<usize>, f64)> = None;
+        let mut consider = |letters: Vec<usize>, score: f64| {
+            if !letters.is_empty() && score > f64::NEG_INFINITY && winner.as_ref().is_none_or(|(_, top)| score > *top) {
+                winner = Some((letters, score));
+            }
+        };
+        for overlap in 1..=head.len().min(tail.len()) {
+            if head.ends_with(&tail[..overlap]) && head.len() + tail.len() - overlap <= max_len {
+                let letters: Vec<usize> = head.iter().chain(&tail[overlap..]).cloned().collect();
+                let score = walk(0, &letters, true);
+                consider(letters, score);
+            }
+        }
+
+        if head.len() + tail.len() <= max_len {
+            let size = self.size();
+            let inner: Vec<usize> = self.alphabet.letters().chain(self.alphabet.space_index()).collect();
+            let anchor = head.last().cloned().unwrap_or(0);
+            // best[c] is the best log probability of the prefix and a middle ending in c, the anchor is the empty middle
+            let mut best = vec![f64::NEG_INFINITY; size];
+            best[anchor] = walk(0, &head, false);
+            // back[t][c] is the character before c on the best middle of length t
+            let mut back: Vec<Vec<usize>> = vec![vec![anchor; size]];
+            for length in 0..=max_len - head.len() - tail.len() {
+                for c in (0..size).filter(|c| best[*c] > f64::NEG_INFINITY) {
+                    let score = best[c] + walk(c, &tail, true);
+                    let mut middle = vec![c];
+                    for t in (2..=length).rev() {
+                        middle.push(back[t][middle[middle.len() - 1]]);
+                    }
+                    middle.reverse();
+                    let middle = if length == 0 { Vec::new() } else { middle };
+                    consider(head.iter().chain(&middle).chain(&tail).cloned().collect(), score);
+                }
+                let mut next = vec![f64::NEG_INFINITY; size];
+                let mut pointers = vec![0; size];
+                for c in (0..size).filter(|c| best[*c] > f64::NEG_INFINITY) {
+                    for n in &inner {
+                        let candidate = best[c] + ln(self.matrix[c][*n]);
+                        if candidate > next[*n] {
+                            next[*n] = candidate;
+                            pointers[*n] = c;
+                        }
+                    }
+                }
+                best = next;
+                back.push(pointers);
+            }
+        }
+
+        Ok(winner.map(|(letters, score)| (letters.iter().map(|c| self.alphabet.char_at(*c)).collect(), score)))
+    }
+
+    /**
+        * Function to convert the letters of a prefix or a suffix to their indices
+        * Fails on anything that isn't a letter or a space of the alphabet, the boundaries included
+    **/
+    pub(crate) fn affix_indices(&self, text: &str) -> Result<Vec<usize>, BigramError> {
+        text.chars()
+            .map(|c| {
+                self.alphabet.index_of(c)
+                    .filter(|i| self.alphabet.letters().contains(i) || Some(*i) == self.alphabet.space_index())
+                    .ok_or(BigramError::InvalidCharacter(c))
+            })
+            .collect()
+    }
+}
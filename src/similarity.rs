@@ -0,0 +1,141 @@
+/*!
+    * Edit distance checks between generated names and the training names, and between the training names themselves
+ **/
+
+use std::collections::HashMap;
+
+/**
+    * Function to compute the Levenshtein distance between two words
+    * The number of single character insertions, deletions and substitutions
+    * needed to turn one word into the other
+**/
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // only the previous row of the distance table is needed
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            current[j] = substitution.min(previous[j] + 1).min(current[j - 1] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/**
+    * The training names bucketed by length
+    * Two names can't be closer than the difference of their lengths,
+    * so a lookup only has to compare against the buckets of nearby lengths
+**/
+pub struct NameIndex {
+    by_length: HashMap<usize, Vec<String>>,
+}
+
+impl NameIndex {
+    /**
+        * Function to build the index, boundary dots are stripped from the names
+    **/
+    pub fn new(names: &[String]) -> NameIndex {
+        let mut by_length: HashMap<usize, Vec<String>> = HashMap::new();
+        for name in names {
+            let name = name.trim_matches('.');
+            by_length.entry(name.chars().count()).or_default().push(name.to_string());
+        }
+        for bucket in by_length.values_mut() {
+            bucket.sort();
+            bucket.dedup();
+        }
+        NameIndex { by_length }
+    }
+
+    /**
+        * Function to check if any indexed name is within max_distance edits of the name
+    **/
+    pub fn has_name_within(&self, name: &str, max_distance: usize) -> bool {
+        let length = name.chars().count();
+        (length.saturating_sub(max_distance)..=length + max_distance)
+            .filter_map(|length| self.by_length.get(&length))
+            .flatten()
+            .any(|other| levenshtein(name, other) <= max_distance)
+    }
+
+    /**
+        * Function to find the edit distance to the nearest indexed name
+        * Buckets are searched outwards from the length of the name and the search
+        * stops once the length difference alone can't beat the best distance
+    **/
+    pub fn nearest_distance(&self, name: &str) -> Option<usize> {
+        let length = name.chars().count();
+        let longest = self.by_length.keys().max()?;
+        let mut best: Option<usize> = None;
+        for difference in 0..=length.max(*longest) {
+            if best.is_some_and(|best| difference >= best) {
+                break;
+            }
+            let mut lengths = vec![length + difference];
+            if difference > 0 && difference <= length {
+                lengths.push(length - difference);
+            }
+            for other in lengths.iter().filter_map(|length| self.by_length.get(length)).flatten() {
+                let distance = levenshtein(name, other);
+                if best.is_none_or(|best| distance < best) {
+                    best = Some(distance);
+                }
+            }
+        }
+        best
+    }
+}
+
+/**
+    * Function to group names that are within max_edit edits of a more common name, like "katharine" of "katherine"
+    * Boundary dots are stripped and repeated names counted once. Names are bucketed by first letter and length,
+    * and a name is only compared with the buckets of its first letter whose lengths are close enough.
+    * Every name of a cluster is close to its first name, so clusters don't chain through short names.
+    * Returns the clusters of more than one name, the most common name first, from the largest cluster
+    * 1. Take the most common name not in a cluster yet, the alphabetically first among equals
+    * 2. Put every other name not in a cluster yet within max_edit edits of it in its cluster
+    * 3. Repeat until every name was taken
+**/
+pub fn near_duplicate_clusters(names: &[String], max_edit: usize) -> Vec<Vec<String>> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for name in names.iter().map(|name| name.trim_matches('.')).filter(|name| !name.is_empty()) {
+        *counts.entry(name).or_insert(0) += 1;
+    }
+    let mut unique: Vec<&str> = counts.keys().cloned().collect();
+    unique.sort_by(|a, b| counts[b].cmp(&counts[a]).then_with(|| a.cmp(b)));
+    let mut buckets: HashMap<(char, usize), Vec<usize>> = HashMap::new();
+    for (i, name) in unique.iter().enumerate() {
+        let first = name.chars().next().expect("empty names are filtered out");
+        buckets.entry((first, name.chars().count())).or_default().push(i);
+    }
+
+    let mut taken = vec![false; unique.len()];
+    let mut clusters: Vec<Vec<String>> = Vec::new();
+    for i in 0..unique.len() {
+        if taken[i] {
+            continue;
+        }
+        taken[i] = true;
+        let first = unique[i].chars().next().expect("empty names are filtered out");
+        let length = unique[i].chars().count();
+        let mut members: Vec<usize> = (length.saturating_sub(max_edit)..=length + max_edit)
+            .filter_map(|length| buckets.get(&(first, length)))
+            .flatten()
+            .cloned()
+            .filter(|j| !taken[*j] && levenshtein(unique[i], unique[*j]) <= max_edit)
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        members.sort();
+        members.iter().for_each(|j| taken[*j] = true);
+        clusters.push(std::iter::once(i).chain(members).map(|j| unique[j].to_string()).collect());
+    }
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+    clusters
+}
@@ -0,0 +1,176 @@
+use bigram::alphabet::{CASED_ALPHABET_SIZE, SPLIT_ALPHABET_SIZE};
+use bigram::{clean_name, clean_name_with, clean_names_with, count_matrix_in, filter_to_alphabet_in, likelihood_of_word, log_likelihood_of_word, Alphabet, BigramModel, CleanOptions, NameWeighting, ALPHABET_SIZE};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn split() -> Alphabet {
+    Alphabet::new('^', '$').unwrap()
+}
+
+#[test]
+fn split_tokens_count_the_end_in_its_own_column() {
+    let counts = count_matrix_in(&[clean_name("ab")], &split());
+    assert_eq!(counts.len(), SPLIT_ALPHABET_SIZE);
+    assert_eq!(counts[0][1], 1.0);
+    assert_eq!(counts[1][2], 1.0);
+    assert_eq!(counts[2][ALPHABET_SIZE], 1.0);
+    assert_eq!(counts[2][0], 0.0);
+}
+
+#[test]
+fn split_matrices_never_enter_the_start_or_leave_the_end() {
+    let model = BigramModel::from_counts(count_matrix_in(&[clean_name("ab")], &split()), 1.0);
+    assert_eq!(model.alphabet, split());
+    assert!(model.matrix.iter().all(|row| row[0] == 0.0));
+    assert!(model.matrix[ALPHABET_SIZE].iter().all(|p| *p == 0.0));
+    assert_eq!(model.matrix[0][ALPHABET_SIZE], 0.0);
+    for row in &model.matrix[..ALPHABET_SIZE] {
+        assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn split_models_generate_and_score_names() {
+    let model = BigramModel::from_counts(count_matrix_in(&[clean_name("ab"), clean_name("ba")], &split()), 0.0);
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..20 {
+        let name = model.sample_name(&mut rng);
+        assert!(!name.is_empty() && name.chars().all(|c| c == 'a' || c == 'b'), "{:?}", name);
+    }
+    // .a, ab, b. each have probability 0.5
+    assert!((log_likelihood_of_word("ab", &model.matrix) - 0.125f64.ln()).abs() < 1e-12);
+}
+
+#[test]
+fn letters_are_not_boundary_tokens() {
+    assert!(Alphabet::new('a', '$').is_err());
+    assert!(Alphabet::new('^', 'Z').is_err());
+    assert!(Alphabet::new(' ', '$').is_err());
+    assert_eq!(Alphabet::default().size(), ALPHABET_SIZE);
+}
+
+// the update fills in the fields of the optional features
+#[allow(clippy::needless_update)]
+fn cased_name(name: &str) -> String {
+    clean_name_with(name, &CleanOptions { case_sensitive: true, ..CleanOptions::default() })
+}
+
+#[test]
+fn case_sensitive_cleaning_keeps_capitals() {
+    assert_eq!(cased_name("McDonald"), ".McDonald.");
+    assert_eq!(clean_name("McDonald"), ".mcdonald.");
+}
+
+#[test]
+fn case_sensitive_alphabets_give_capitals_their_own_rows() {
+    let cased = Alphabet::default().with_case(true);
+    assert_eq!(cased.size(), CASED_ALPHABET_SIZE);
+    assert_eq!(cased.index_of('A'), Some(ALPHABET_SIZE));
+    assert_eq!(cased.char_at(CASED_ALPHABET_SIZE - 1), 'Z');
+    assert_eq!(Alphabet::default().index_of('A'), None);
+    assert_eq!(Alphabet::for_size(CASED_ALPHABET_SIZE), cased);
+
+    let split = split().with_case(true);
+    assert_eq!(split.end_index(), CASED_ALPHABET_SIZE);
+    assert_eq!(Alphabet::for_size(split.size()), split);
+}
+
+#[test]
+fn case_sensitive_models_generate_internal_capitals() {
+    let cased = Alphabet::default().with_case(true);
+    let names = vec![cased_name("DeShawn"), cased_name("McDonald")];
+    let (kept, dropped) = filter_to_alphabet_in(names.clone(), &Alphabet::default());
+    assert_eq!((kept.len(), dropped), (0, 2));
+
+    let model = BigramModel::from_counts(count_matrix_in(&names, &cased), 0.0);
+    assert_eq!(model.alphabet, cased);
+    let mut rng = StdRng::seed_from_u64(3);
+    let capitals = (0..50).map(|_| model.sample_name(&mut rng)).filter(|name| name.chars().skip(1).any(|c| c.is_ascii_uppercase())).count();
+    assert!(capitals > 0);
+    // the scored word keeps its case, so the lower case spelling was never seen
+    assert!(log_likelihood_of_word("McDonald", &model.matrix).is_finite());
+    assert_eq!(log_likelihood_of_word("mcdonald", &model.matrix), f64::NEG_INFINITY);
+}
+
+// the update fills in the fields of the optional features
+#[allow(clippy::needless_update)]
+fn spaced_name(name: &str) -> String {
+    clean_name_with(name, &CleanOptions { keep_spaces: true, ..CleanOptions::default() })
+}
+
+#[test]
+fn keeping_spaces_collapses_runs_of_whitespace() {
+    assert_eq!(spaced_name("  Mary   Jane "), ".mary jane.");
+    assert_eq!(spaced_name("Mary - Jane"), ".mary jane.");
+    assert_eq!(clean_name("Mary Jane"), ".maryjane.");
+}
+
+#[test]
+fn alphabets_with_spaces_have_their_own_size() {
+    let spaced = Alphabet::default().with_spaces(true);
+    assert_eq!(spaced.size(), SPLIT_ALPHABET_SIZE + 1);
+    assert_eq!(spaced.space_index(), Some(ALPHABET_SIZE));
+    assert_eq!(spaced.end_index(), ALPHABET_SIZE + 1);
+    assert_eq!(spaced.char_at(ALPHABET_SIZE), ' ');
+    // a space is only ever between two words
+    assert!(!spaced.is_allowed(0, ALPHABET_SIZE));
+    assert!(!spaced.is_allowed(ALPHABET_SIZE, ALPHABET_SIZE));
+    assert!(!spaced.is_allowed(ALPHABET_SIZE, spaced.end_index()));
+    assert!(spaced.is_allowed(1, ALPHABET_SIZE));
+    assert_eq!(Alphabet::default().index_of(' '), None);
+    // the matrix alone can't tell the tokens apart, only that there are spaces
+    assert!(Alphabet::for_size(spaced.size()).spaces);
+    assert_eq!(Alphabet::for_size(spaced.with_case(true).size()), split().with_case(true).with_spaces(true));
+}
+
+#[test]
+fn models_with_spaces_generate_phrases() {
+    let spaced = Alphabet::default().with_spaces(true);
+    let names = vec![spaced_name("mary jane"), spaced_name("anna lee")];
+    let model = BigramModel::from_counts(count_matrix_in(&names, &spaced), 0.0);
+    let mut rng = StdRng::seed_from_u64(9);
+    let phrases = (0..50).map(|_| model.sample_name(&mut rng)).filter(|name| name.contains(' ')).count();
+    assert!(phrases > 0);
+    assert!(log_likelihood_of_word("mary jane", &model.matrix).is_finite());
+    assert_eq!(log_likelihood_of_word("maryjane", &model.matrix), f64::NEG_INFINITY);
+}
+
+fn excluding_z() -> CleanOptions {
+    CleanOptions { exclude: vec!['z'], ..CleanOptions::default() }
+}
+
+#[test]
+fn cleaning_strips_excluded_letters_in_either_case() {
+    assert_eq!(clean_name_with("Zuzana", &excluding_z()), ".uana.");
+    let cased = CleanOptions { case_sensitive: true, ..excluding_z() };
+    assert_eq!(clean_name_with("Zuzana", &cased), ".Zuana.");
+}
+
+#[test]
+fn excluded_letters_have_empty_rows_and_columns() {
+    let alphabet = Alphabet::default().with_excluded(&['z']);
+    assert_eq!(alphabet.size(), ALPHABET_SIZE);
+    assert_eq!(alphabet.excluded_chars(), vec!['z']);
+    let names = clean_names_with(&["Zoe".to_string(), "Liza".to_string(), "Anna".to_string()], &excluding_z());
+    let model = BigramModel::from_names(&names, NameWeighting::Frequency, alphabet, 1.0).unwrap();
+    let z = 26;
+    assert!(model.matrix[z].iter().all(|p| *p == 0.0));
+    assert!(model.matrix.iter().all(|row| row[z] == 0.0));
+    for (i, row) in model.matrix.iter().enumerate().filter(|(i, _)| *i != z) {
+        assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12, "row {}", i);
+    }
+
+    let mut rng = StdRng::seed_from_u64(3);
+    assert!((0..200).all(|_| !model.sample_name(&mut rng).contains('z')));
+}
+
+#[test]
+fn scoring_reports_excluded_letters_as_out_of_vocabulary() {
+    let alphabet = Alphabet::default().with_excluded(&['z']);
+    let names = clean_names_with(&["Anna".to_string(), "Liza".to_string()], &excluding_z());
+    let model = BigramModel::from_names(&names, NameWeighting::Frequency, alphabet, 1.0).unwrap();
+    assert_eq!(model.out_of_vocabulary("Zizi"), vec!['z']);
+    assert!(model.out_of_vocabulary("anna").is_empty());
+    assert_eq!(alphabet.index_of('z'), None);
+    assert_eq!(likelihood_of_word("liza", &model.matrix), 0.0);
+}
@@ -0,0 +1,37 @@
+use bigram::attribution::{bigram_sources, covering_names};
+use bigram::{clean_name, Alphabet};
+
+fn corpus(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| clean_name(n)).collect()
+}
+
+#[test]
+fn every_bigram_lists_the_names_holding_it() {
+    let names = corpus(&["ann", "anna", "bob", "ann"]);
+    let sources = bigram_sources(".nab.", &names, &Alphabet::default());
+    let listed: Vec<(char, char, Vec<&str>)> = sources.iter()
+        .map(|source| (source.previous, source.next, source.names.iter().map(String::as_str).collect()))
+        .collect();
+    assert_eq!(listed, vec![
+        ('.', 'n', vec![]),
+        ('n', 'a', vec!["anna"]),
+        ('a', 'b', vec![]),
+        ('b', '.', vec!["bob"]),
+    ]);
+}
+
+#[test]
+fn a_training_name_covers_itself() {
+    let names = corpus(&["ann", "anna", "bob"]);
+    let sources = bigram_sources(".anna.", &names, &Alphabet::default());
+    assert_eq!(covering_names(&sources), vec!["anna"]);
+}
+
+#[test]
+fn cover_combines_names_for_a_new_name() {
+    let names = corpus(&["bo", "ann", "nab"]);
+    // .b and bo come from bo and n. from ann, no name holds on
+    let sources = bigram_sources(".bon.", &names, &Alphabet::default());
+    assert!(sources.iter().find(|source| (source.previous, source.next) == ('o', 'n')).unwrap().names.is_empty());
+    assert_eq!(covering_names(&sources), vec!["bo", "ann"]);
+}
@@ -0,0 +1,106 @@
+use bigram::chain::{effective_bigrams, expected_name_length, joint_bigram_entropy, stationary_distribution};
+use bigram::{clean_names_with, Alphabet, BigramModel, CleanOptions, NameWeighting, ALPHABET_SIZE};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn geometric_names_have_the_geometric_mean_length() {
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    // start with 'a', then repeat it or stop with even odds
+    matrix[0][1] = 1.0;
+    matrix[1][1] = 0.5;
+    matrix[1][0] = 0.5;
+    assert!((expected_name_length(&matrix) - 2.0).abs() < 1e-12);
+}
+
+#[test]
+fn chains_that_never_end_have_infinite_length() {
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    matrix[0][1] = 1.0;
+    matrix[1][2] = 1.0;
+    matrix[2][1] = 1.0;
+    assert_eq!(expected_name_length(&matrix), f64::INFINITY);
+}
+
+#[test]
+fn expected_length_matches_the_sampled_mean() {
+    let model = BigramModel::train_from_names(&["emma", "olivia", "ava", "isabella", "sophia"], 0.1).unwrap();
+    let mut rng = StdRng::seed_from_u64(1);
+    let samples = 20000;
+    let total: usize = (0..samples).map(|_| model.sample_name(&mut rng).len()).sum();
+    let sampled = total as f64 / samples as f64;
+    let expected = expected_name_length(&model.matrix);
+    assert!((sampled - expected).abs() / expected < 0.03, "sampled {} expected {}", sampled, expected);
+}
+
+#[test]
+fn expected_length_counts_the_words_after_a_space() {
+    let names: Vec<String> = ["mary jane", "anna", "jean luc", "emma", "ava sophia", "mia"].iter().map(|name| name.to_string()).collect();
+    let options = CleanOptions { keep_spaces: true, ..CleanOptions::default() };
+    let model = BigramModel::from_names(&clean_names_with(&names, &options), NameWeighting::Frequency, Alphabet::default().with_spaces(true), 0.1).unwrap();
+    let mut rng = StdRng::seed_from_u64(2);
+    let samples = 20000;
+    let total: usize = (0..samples).map(|_| model.sample_name(&mut rng).chars().count()).sum();
+    let sampled = total as f64 / samples as f64;
+    let expected = expected_name_length(&model.matrix);
+    assert!((sampled - expected).abs() / expected < 0.03, "sampled {} expected {}", sampled, expected);
+}
+
+#[test]
+fn periodic_chains_still_converge() {
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    // 'a' and 'b' alternate forever, the other letters only pass through
+    matrix[1][2] = 1.0;
+    matrix[2][1] = 1.0;
+    let stationary = stationary_distribution(&matrix);
+    assert_eq!(stationary[0], 0.0);
+    assert!((stationary[1] - 0.5).abs() < 1e-9);
+    assert!((stationary[2] - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn stationary_distribution_is_a_fixed_point_of_the_letters() {
+    let model = BigramModel::train_from_names(&["emma", "olivia", "ava", "isabella", "sophia"], 0.1).unwrap();
+    let stationary = stationary_distribution(&model.matrix);
+    assert!((stationary.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    for to in 1..ALPHABET_SIZE {
+        let inflow: f64 = (1..ALPHABET_SIZE)
+            .map(|from| {
+                let interior: f64 = model.matrix[from][1..].iter().sum();
+                stationary[from] * model.matrix[from][to] / interior
+            })
+            .sum();
+        assert!((inflow - stationary[to]).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn a_single_name_has_one_effective_bigram_per_transition() {
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    // always "ab", three transitions visited once each
+    matrix[0][1] = 1.0;
+    matrix[1][2] = 1.0;
+    matrix[2][0] = 1.0;
+    assert!((joint_bigram_entropy(&matrix) - 3f64.log2()).abs() < 1e-12);
+    assert!((effective_bigrams(&matrix) - 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn rows_are_weighted_by_how_often_they_are_visited() {
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    // "a" or "b" with even odds, every transition has a joint probability of 1/4
+    matrix[0][1] = 0.5;
+    matrix[0][2] = 0.5;
+    matrix[1][0] = 1.0;
+    matrix[2][0] = 1.0;
+    assert!((effective_bigrams(&matrix) - 4.0).abs() < 1e-9);
+}
+
+#[test]
+fn chains_that_never_end_have_infinite_joint_entropy() {
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    matrix[0][1] = 1.0;
+    matrix[1][2] = 1.0;
+    matrix[2][1] = 1.0;
+    assert_eq!(joint_bigram_entropy(&matrix), f64::INFINITY);
+}
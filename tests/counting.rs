@@ -0,0 +1,47 @@
+use bigram::{clean_name, count_bigrams, count_matrix, BigramModel};
+
+fn cleaned(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| clean_name(n)).collect()
+}
+
+#[test]
+fn boundary_and_interior_transitions_land_in_the_right_cells() {
+    let counts = count_matrix(&cleaned(&["ab", "ba"]));
+    // ".a" and ".b" start the names
+    assert_eq!(counts[0][1], 1.0);
+    assert_eq!(counts[0][2], 1.0);
+    // "a." and "b." end them
+    assert_eq!(counts[1][0], 1.0);
+    assert_eq!(counts[2][0], 1.0);
+    // interior "ab" and "ba"
+    assert_eq!(counts[1][2], 1.0);
+    assert_eq!(counts[2][1], 1.0);
+    // nothing else was counted, and never the dot-dot cell
+    assert_eq!(counts[0][0], 0.0);
+    assert_eq!(counts.iter().flatten().sum::<f64>(), 6.0);
+}
+
+#[test]
+fn last_letter_uses_the_last_index() {
+    let counts = count_matrix(&cleaned(&["zz"]));
+    assert_eq!(counts[0][26], 1.0);
+    assert_eq!(counts[26][26], 1.0);
+    assert_eq!(counts[26][0], 1.0);
+}
+
+#[test]
+fn repeated_bigrams_accumulate() {
+    let counts = count_matrix(&cleaned(&["anna", "ana"]));
+    assert_eq!(counts[0][1], 2.0);
+    assert_eq!(counts[1][14], 2.0);
+    assert_eq!(counts[14][14], 1.0);
+    assert_eq!(counts[14][1], 2.0);
+    assert_eq!(counts[1][0], 2.0);
+}
+
+#[test]
+fn string_counts_fill_the_same_cells_as_the_matrix() {
+    let names = cleaned(&["emma", "olivia", "ava", "isabella"]);
+    let model = BigramModel::from_string_counts(&count_bigrams(&names), 1.0);
+    assert_eq!(model.counts, count_matrix(&names));
+}
@@ -0,0 +1,52 @@
+use bigram::eval::{ks_critical_value, learning_curve, length_ks_statistic, perplexity};
+use bigram::{clean_name, clean_names, count_matrix, BigramModel, BoundaryMode};
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[test]
+fn same_lengths_give_a_zero_statistic() {
+    let training: Vec<String> = ["ann", "bob", "emma"].iter().map(|n| clean_name(n)).collect();
+    assert_eq!(length_ks_statistic(&names(&["kim", "zoe", "ella"]), &training), Some(0.0));
+}
+
+#[test]
+fn disjoint_lengths_give_a_statistic_of_one() {
+    assert_eq!(length_ks_statistic(&names(&["a", "bo"]), &names(&["emma", "sophia"])), Some(1.0));
+}
+
+#[test]
+fn statistic_is_the_largest_cdf_difference() {
+    // up to length 2 the shares are 3/4 and 1/4
+    let statistic = length_ks_statistic(&names(&["a", "bo", "al", "emma"]), &names(&["bo", "ann", "emma", "ella"])).unwrap();
+    assert!((statistic - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn empty_sets_have_no_statistic() {
+    assert_eq!(length_ks_statistic(&[], &names(&["ann"])), None);
+    assert!(ks_critical_value(1000, 1000) < ks_critical_value(10, 10));
+}
+
+#[test]
+fn learning_curves_grow_to_the_whole_training_set() {
+    let training = clean_names(&names(&["emma", "olivia", "ava", "isabella", "sophia", "mia", "amelia", "harper", "evelyn", "abigail", "emily"]));
+    let test = clean_names(&names(&["ella", "avery", "sofia"]));
+    let train = |names: &[String]| BigramModel::from_counts(count_matrix(names), 0.5);
+    let curve = learning_curve(&training, &test, 4, BoundaryMode::Inclusive, train);
+    let sizes: Vec<usize> = curve.iter().map(|point| point.names).collect();
+    assert_eq!(sizes, vec![3, 6, 9, 11]);
+    let full = perplexity(&test, &train(&training).matrix, BoundaryMode::Inclusive).unwrap();
+    assert!((curve[3].perplexity - full).abs() < 1e-12);
+    assert!(curve[3].perplexity < curve[0].perplexity);
+}
+
+#[test]
+fn nothing_to_score_has_no_perplexity() {
+    let model = BigramModel::from_counts(count_matrix(&clean_names(&names(&["emma", "ava"]))), 0.5);
+    assert_eq!(perplexity(&[], &model.matrix, BoundaryMode::Inclusive), None);
+    // a single letter has only its boundary transitions
+    assert_eq!(perplexity(&clean_names(&names(&["a"])), &model.matrix, BoundaryMode::Interior), None);
+    assert!(perplexity(&clean_names(&names(&["a"])), &model.matrix, BoundaryMode::Inclusive).is_some());
+}
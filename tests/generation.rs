@@ -0,0 +1,70 @@
+use bigram::chain::expected_name_length;
+use bigram::{clean_names, clean_names_with, Alphabet, BigramModel, CleanOptions, NameWeighting, SampleOptions, MAX_NAME_LEN};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const SEEDS: u64 = 200;
+const NAMES_PER_SEED: usize = 50;
+
+fn bundled() -> Vec<String> {
+    include_str!("../files/names.txt").lines().map(|name| name.to_string()).collect()
+}
+
+fn train_in(names: &[String], alphabet: Alphabet, smoothing: f64) -> BigramModel {
+    BigramModel::from_names(names, NameWeighting::Frequency, alphabet, smoothing).unwrap()
+}
+
+// every name of every seed is non-empty, short enough, and only letters of the alphabet
+fn assert_generation_is_sound(model: &BigramModel, options: &SampleOptions) {
+    // a finite expected length means every letter can reach the end, so sampling always terminates
+    let expected = expected_name_length(&model.matrix);
+    assert!(expected.is_finite(), "expected length {}", expected);
+    let letters = model.alphabet.letters();
+    for seed in 0..SEEDS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..NAMES_PER_SEED {
+            let name = model.sample_name_with(&mut rng, options);
+            assert!(!name.is_empty(), "seed {} sampled an empty name", seed);
+            // far past the longest bundled name, a name this long means the chain lost its way to the end
+            assert!(name.chars().count() < MAX_NAME_LEN, "seed {} sampled {:?}", seed, name);
+            assert!(!name.starts_with(' ') && !name.ends_with(' ') && !name.contains("  "), "seed {} sampled {:?}", seed, name);
+            for c in name.chars() {
+                let inside = model.alphabet.index_of(c).is_some_and(|i| letters.contains(&i) || Some(i) == model.alphabet.space_index());
+                assert!(inside, "seed {} sampled {:?} with {:?}", seed, name, c);
+            }
+        }
+    }
+}
+
+#[test]
+fn smoothed_models_always_generate_sound_names() {
+    let model = train_in(&clean_names(&bundled()), Alphabet::default(), 1.0);
+    assert_generation_is_sound(&model, &SampleOptions::default());
+    assert_generation_is_sound(&model, &SampleOptions { min_step_prob: 0.01, start_bias: vec![('x', 50.0)], ..SampleOptions::default() });
+}
+
+#[test]
+fn unsmoothed_models_always_generate_sound_names() {
+    // rows of letters never seen are all zeros, the chain must never step into them
+    let model = train_in(&clean_names(&bundled()), Alphabet::default(), 0.0);
+    assert_generation_is_sound(&model, &SampleOptions::default());
+}
+
+#[test]
+fn split_and_excluding_alphabets_always_generate_sound_names() {
+    let options = CleanOptions { exclude: vec!['e', 'q'], ..CleanOptions::default() };
+    let alphabet = Alphabet::new('^', '$').unwrap().with_excluded(&['e', 'q']);
+    let model = train_in(&clean_names_with(&bundled(), &options), alphabet, 0.5);
+    assert_generation_is_sound(&model, &SampleOptions { sanitize: true, ..SampleOptions::default() });
+}
+
+#[test]
+fn models_with_spaces_always_generate_sound_names() {
+    // one line in ten has two names, more would make phrases that run past the cap
+    let names: Vec<String> = bundled().chunks(10)
+        .flat_map(|chunk| std::iter::once(chunk[..chunk.len().min(2)].join(" ")).chain(chunk.iter().skip(2).cloned()))
+        .collect();
+    let options = CleanOptions { keep_spaces: true, ..CleanOptions::default() };
+    let model = train_in(&clean_names_with(&names, &options), Alphabet::default().with_spaces(true), 0.1);
+    assert_generation_is_sound(&model, &SampleOptions::default());
+}
@@ -0,0 +1,25 @@
+use bigram::BigramModel;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const CORPUS: [&str; 8] = ["emma", "olivia", "ava", "isabella", "sophia", "mia", "amelia", "harper"];
+
+fn generate(seed: u64, count: usize) -> Vec<String> {
+    let model = BigramModel::train_from_names(&CORPUS, 0.1).unwrap();
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count).map(|_| model.sample_name(&mut rng)).collect()
+}
+
+#[test]
+fn generated_names_are_pinned_for_a_fixed_seed() {
+    let expected = vec![
+        "igqj", "hnva", "smfcne", "iahsmia", "hqellivwwqemfa",
+        "ypha", "hixvisrpelia", "mixdlia", "emma", "mrphiva",
+    ];
+    assert_eq!(generate(42, 10), expected);
+}
+
+#[test]
+fn same_seed_generates_same_names() {
+    assert_eq!(generate(7, 20), generate(7, 20));
+}
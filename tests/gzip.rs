@@ -0,0 +1,44 @@
+#![cfg(feature = "gzip")]
+
+use std::io::Write;
+use std::path::PathBuf;
+use bigram::{read_corpus, read_corpus_with};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bigram-{}-{}", std::process::id(), name))
+}
+
+fn write_gzip(path: &PathBuf, text: &str) {
+    let mut encoder = GzEncoder::new(std::fs::File::create(path).unwrap(), Compression::default());
+    encoder.write_all(text.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+}
+
+#[test]
+fn gz_extension_is_decompressed() {
+    let path = temp_path("names.txt.gz");
+    write_gzip(&path, "emma\nolivia\nava\n");
+    let lines = read_corpus(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(lines.unwrap(), vec!["emma", "olivia", "ava"]);
+}
+
+#[test]
+fn gzip_flag_decompresses_any_file() {
+    let path = temp_path("names.bin");
+    write_gzip(&path, "emma\nolivia\n");
+    let lines = read_corpus_with(&path, true);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(lines.unwrap(), vec!["emma", "olivia"]);
+}
+
+#[test]
+fn plain_text_is_not_valid_gzip() {
+    let path = temp_path("plain.gz");
+    std::fs::write(&path, "emma\n").unwrap();
+    let lines = read_corpus(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(lines.unwrap_err().to_string().contains("could not decompress"));
+}
@@ -0,0 +1,41 @@
+use bigram::mixture::LengthBuckets;
+use bigram::{clean_name, Alphabet, BigramModel, NameWeighting, SampleOptions};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn train(names: &[String]) -> Result<BigramModel, bigram::BigramError> {
+    BigramModel::from_names(names, NameWeighting::Frequency, Alphabet::default(), 0.1)
+}
+
+fn corpus() -> Vec<String> {
+    ["al", "bo", "ann", "emma", "olivia", "isabella", "sophia", "amelia"].iter().map(|n| clean_name(n)).collect()
+}
+
+#[test]
+fn buckets_split_the_corpus_at_the_bounds() {
+    let buckets = LengthBuckets::train(&corpus(), &[3, 6], train).unwrap();
+    assert_eq!(buckets.ranges, vec![1..=3, 4..=6, 7..=usize::MAX]);
+    assert_eq!(buckets.mixture.models.len(), 3);
+}
+
+#[test]
+fn empty_buckets_are_left_out() {
+    let buckets = LengthBuckets::train(&corpus(), &[1, 3], train).unwrap();
+    assert_eq!(buckets.ranges, vec![2..=3, 4..=usize::MAX]);
+}
+
+#[test]
+fn names_keep_to_the_lengths_of_their_bucket() {
+    let buckets = LengthBuckets::train(&corpus(), &[3, 6], train).unwrap();
+    let mut rng = StdRng::seed_from_u64(12);
+    for _ in 0..200 {
+        let (index, name) = buckets.sample_matching(&mut rng, &SampleOptions::default(), |_| true).unwrap();
+        assert!(buckets.ranges[index].contains(&name.len()), "{name} is outside of bucket {index}");
+    }
+}
+
+#[test]
+fn bounds_have_to_increase() {
+    assert!(LengthBuckets::train(&corpus(), &[6, 3], train).is_err());
+    assert!(LengthBuckets::train(&corpus(), &[0], train).is_err());
+}
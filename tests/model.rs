@@ -0,0 +1,205 @@
+use bigram::{clean_name, clean_names, Alphabet, BigramError, BigramModel, NameWeighting, SmoothingMethod, ALPHABET_SIZE};
+
+fn train(names: &[&str], smoothing: f64) -> BigramModel {
+    BigramModel::train_from_names(names, smoothing).unwrap()
+}
+
+#[test]
+fn unseen_context_is_uniform_without_backoff() {
+    let model = train(&["ab", "ba"], 1.0);
+    let z = &model.matrix[26];
+    assert!(z.iter().all(|p| (p - 1.0 / ALPHABET_SIZE as f64).abs() < 1e-12));
+}
+
+#[test]
+fn unigram_backoff_replaces_unseen_context() {
+    let mut model = train(&["ab", "ba"], 1.0);
+    let seen = model.matrix[1].clone();
+    model.apply_unigram_backoff();
+
+    let z = &model.matrix[26];
+    assert_eq!(z, &model.unigram_distribution());
+    // '.', 'a' and 'b' each follow two bigrams, everything else only gets smoothing
+    assert!((z[1] - 3.0 / 33.0).abs() < 1e-12);
+    assert!((z[5] - 1.0 / 33.0).abs() < 1e-12);
+    assert!((z.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    // rows with observed counts are left alone
+    assert_eq!(model.matrix[1], seen);
+}
+
+#[test]
+fn constraints_forbid_and_boost_transitions_after_smoothing() {
+    let mut model = train(&["qxa", "qua"], 1.0);
+    model.apply_constraints(&[('q', 'x', 0.0), ('q', 'u', 2.0)]).unwrap();
+    let q = &model.matrix[17];
+    assert_eq!(q[24], 0.0);
+    assert!((q.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    // u had 1 count + 1 smoothing and gets doubled against 25 other cells of smoothing
+    assert!((q[21] - 4.0 / 29.0).abs() < 1e-12);
+    assert!(model.apply_constraints(&[('q', '!', 0.0)]).is_err());
+}
+
+#[test]
+fn empty_corpus_is_an_error() {
+    let empty = BigramModel::from_names(&[], NameWeighting::Frequency, Alphabet::default(), 1.0);
+    assert_eq!(empty.unwrap_err(), BigramError::EmptyCorpus);
+
+    // every line cleans to nothing, so nothing is left to train on
+    let lines: Vec<String> = ["", "  ", "123", "--"].iter().map(|line| line.to_string()).collect();
+    let cleaned = clean_names(&lines);
+    assert!(cleaned.is_empty());
+    let filtered = BigramModel::from_names(&cleaned, NameWeighting::Frequency, Alphabet::default(), 1.0);
+    assert_eq!(filtered.unwrap_err().to_string(), "no trainable names found, every line was empty or filtered out");
+}
+
+#[test]
+fn from_names_keeps_the_alphabet() {
+    let names: Vec<String> = ["ab", "ba"].iter().map(|n| clean_name(n)).collect();
+    let split = Alphabet::new('^', '$').unwrap();
+    let model = BigramModel::from_names(&names, NameWeighting::Frequency, split, 1.0).unwrap();
+    assert_eq!(model.alphabet, split);
+    assert_eq!(model.size(), split.size());
+}
+
+#[test]
+fn confidence_weighting_leans_on_the_unigram_in_sparse_rows() {
+    // every column is seen once, so the unigram distribution is a third each for '.', 'a' and 'b'
+    let mut model = train(&["ab"], 0.0);
+    model.apply_smoothing_method(SmoothingMethod::ConfidenceWeighted(1.0));
+    // 'a' was seen once, so lambda is 1 / (1 + 1)
+    assert!((model.matrix[1][2] - (0.5 + 0.5 / 3.0)).abs() < 1e-12);
+    assert!((model.matrix[1][1] - 0.5 / 3.0).abs() < 1e-12);
+    // the start can't go straight to the end, so its row is renormalized without that cell
+    assert_eq!(model.matrix[0][0], 0.0);
+    assert!((model.matrix[0][1] - 0.8).abs() < 1e-12);
+    // 'z' was never a context, so it is all unigram
+    assert!((model.matrix[26][1] - 1.0 / 3.0).abs() < 1e-12);
+
+    let once = model.matrix.clone();
+    model.apply_smoothing_method(SmoothingMethod::ConfidenceWeighted(1.0));
+    assert_eq!(model.matrix, once);
+}
+
+#[test]
+fn fixed_interpolation_uses_the_same_lambda_in_every_row() {
+    let mut model = train(&["ab", "aab"], 0.0);
+    model.apply_smoothing_method(SmoothingMethod::Interpolated(1.0));
+    // with all the weight on the bigrams the seen rows are their observed distribution
+    assert_eq!(model.matrix[..3], train(&["ab", "aab"], 0.0).matrix[..3]);
+    // rows never seen as a context have nothing to trust, so they are the unigram distribution
+    for (p, unigram) in model.matrix[3].iter().zip(model.unigram_distribution()) {
+        assert!((p - unigram).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn model_hash_is_stable_and_follows_the_matrix() {
+    let model = train(&["ann", "bob"], 1.0);
+    assert_eq!(model.model_hash(), train(&["ann", "bob"], 1.0).model_hash());
+    assert_ne!(model.model_hash(), train(&["ann", "bob"], 0.5).model_hash());
+    // the counts aren't hashed, the boundary tokens are
+    let mut recounted = model.clone();
+    recounted.counts[1][2] += 1.0;
+    assert_eq!(recounted.model_hash(), model.model_hash());
+    let retokened = BigramModel { alphabet: Alphabet::new('#', '#').unwrap(), ..model.clone() };
+    assert_ne!(retokened.model_hash(), model.model_hash());
+    // excluded letters score as out of vocabulary, so they are hashed even with the same matrix
+    let excluding = BigramModel { alphabet: Alphabet::default().with_excluded(&['z']), ..model.clone() };
+    assert_ne!(excluding.model_hash(), model.model_hash());
+    let excluding_other = BigramModel { alphabet: Alphabet::default().with_excluded(&['q']), ..model.clone() };
+    assert_ne!(excluding_other.model_hash(), excluding.model_hash());
+}
+
+#[test]
+fn inverse_frequency_models_hash_the_same_every_time() {
+    // distinct names would add up in the order of a hash map, which changes with every map
+    let names = clean_names(&include_str!("../files/names.txt").lines().map(String::from).collect::<Vec<String>>());
+    let hash = || BigramModel::from_names(&names, NameWeighting::InverseSqrtFrequency, Alphabet::default(), 1.0).unwrap().model_hash();
+    let first = hash();
+    assert!((0..5).all(|_| hash() == first));
+}
+
+#[test]
+fn model_hash_is_pinned() {
+    // a fixed value, so a change to the hashing shows up as a broken cache key
+    assert_eq!(train(&["ann", "bob"], 1.0).model_hash(), 0x8d77_ed14_8f6b_cd14);
+}
+
+#[test]
+fn train_from_names_cleans_and_counts_in_memory() {
+    let model = BigramModel::train_from_names(&["Anna!", "  bob", "", "42"], 1.0).unwrap();
+    let expected = BigramModel::from_counts(bigram::count_matrix(&[clean_name("anna"), clean_name("bob")]), 1.0);
+    assert_eq!(model.counts, expected.counts);
+    assert_eq!(model.matrix, expected.matrix);
+}
+
+#[test]
+fn train_from_names_drops_names_outside_of_the_alphabet() {
+    let model = BigramModel::train_from_names(&["zoé", "anna"], 1.0).unwrap();
+    let expected = BigramModel::from_counts(bigram::count_matrix(&[clean_name("anna")]), 1.0);
+    assert_eq!(model.counts, expected.counts);
+}
+
+#[test]
+fn train_from_names_fails_without_names() {
+    assert_eq!(BigramModel::train_from_names(&[], 1.0).unwrap_err(), BigramError::EmptyCorpus);
+    assert_eq!(BigramModel::train_from_names(&["", "42", "é"], 1.0).unwrap_err(), BigramError::EmptyCorpus);
+}
+
+#[test]
+fn flat_matrix_is_row_major_and_round_trips() {
+    let model = train(&["ann", "bob"], 1.0);
+    let (data, size) = model.to_flat();
+    assert_eq!(size, ALPHABET_SIZE);
+    assert_eq!(data.len(), size * size);
+    assert_eq!(data[size + 14], model.matrix[1][14]);
+    let restored = BigramModel::from_flat(&data, size).unwrap();
+    assert_eq!(restored.matrix, model.matrix);
+    assert_eq!(restored.alphabet, model.alphabet);
+    assert_eq!(restored.model_hash(), model.model_hash());
+}
+
+#[test]
+fn flat_matrix_must_be_square_in_an_alphabet_size() {
+    assert!(BigramModel::from_flat(&[0.0; 10], ALPHABET_SIZE).is_err());
+    assert!(BigramModel::from_flat(&[0.0; 25], 5).is_err());
+    assert!(BigramModel::from_flat(&vec![0.0; ALPHABET_SIZE * ALPHABET_SIZE], ALPHABET_SIZE).is_ok());
+}
+
+#[test]
+fn boundary_weight_scales_the_end_without_touching_the_interior() {
+    let names = clean_names(&["emma", "olivia", "ava", "isabella", "sophia"].map(String::from));
+    let plain = BigramModel::from_names(&names, NameWeighting::Frequency, Alphabet::default(), 0.0).unwrap();
+    let mut weighted = plain.clone();
+    weighted.apply_boundary_weight(1.0);
+    assert_eq!(weighted.matrix, plain.matrix);
+
+    weighted.apply_boundary_weight(0.5);
+    let expected = |model: &BigramModel| bigram::chain::expected_name_length(&model.matrix);
+    assert!(expected(&weighted) > expected(&plain));
+    // 'a' ends names less often, but goes on to 'v' and 'b' just as often relative to each other
+    let a = 1;
+    let ratio = |model: &BigramModel| model.matrix[a][22] / model.matrix[a][2];
+    assert!((ratio(&weighted) - ratio(&plain)).abs() < 1e-12);
+    assert!(weighted.matrix[a][0] < plain.matrix[a][0]);
+}
+
+#[test]
+fn boundary_weight_keeps_the_counts_raw_and_survives_renormalizing() {
+    let names = clean_names(&["emma", "olivia", "ava", "isabella", "sophia"].map(String::from));
+    let plain = BigramModel::from_names(&names, NameWeighting::Frequency, Alphabet::default(), 0.0).unwrap();
+    let mut weighted = plain.clone();
+    weighted.apply_boundary_weight(0.5);
+    assert_eq!(weighted.counts, plain.counts);
+    let matrix = weighted.matrix.clone();
+    weighted.renormalize();
+    assert_eq!(weighted.matrix, matrix);
+
+    let path = std::env::temp_dir().join(format!("bigram-boundary-weight-{}.json", std::process::id()));
+    weighted.save(&path).unwrap();
+    let loaded = BigramModel::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.boundary_weight, 0.5);
+    assert_eq!(loaded.counts, plain.counts);
+}
@@ -0,0 +1,47 @@
+use bigram::predicate::{parse_predicate, parse_predicate_in};
+
+#[test]
+fn terms_are_joined_with_and() {
+    let predicate = parse_predicate("len>=4 && starts=m && !contains=xx").unwrap();
+    assert!(predicate.matches("mira"));
+    assert!(!predicate.matches("mia"));
+    assert!(!predicate.matches("amira"));
+    assert!(!predicate.matches("maxxa"));
+}
+
+#[test]
+fn length_comparisons() {
+    let check = |expression: &str, name: &str| parse_predicate(expression).unwrap().matches(name);
+    assert!(check("len<4", "ava"));
+    assert!(!check("len<4", "emma"));
+    assert!(check("len>3", "emma"));
+    assert!(check("len=4", "emma"));
+    assert!(!check("len=4", "ava"));
+    assert!(check("len<=3", "ava"));
+}
+
+#[test]
+fn text_predicates() {
+    let predicate = parse_predicate("ends=a && not-contains=q && contains=li").unwrap();
+    assert!(predicate.matches("olivia"));
+    assert!(!predicate.matches("oliver"));
+}
+
+#[test]
+fn invalid_expressions_are_rejected() {
+    assert!(parse_predicate("len>>4").is_err());
+    assert!(parse_predicate("len<0").is_err());
+    assert!(parse_predicate("starts=").is_err());
+    assert!(parse_predicate("rhymes=a").is_err());
+    assert!(parse_predicate("m").is_err());
+}
+
+#[test]
+fn case_sensitive_text_keeps_its_capitals() {
+    let predicate = parse_predicate_in("starts=M && !contains=xX", true).unwrap();
+    assert!(predicate.matches("Mira"));
+    assert!(!predicate.matches("mira"));
+    assert!(!predicate.matches("MaxXa"));
+    // without case sensitivity names are lowercase, so the text is too
+    assert!(parse_predicate_in("starts=M", false).unwrap().matches("mira"));
+}
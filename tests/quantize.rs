@@ -0,0 +1,88 @@
+use std::collections::{HashMap, HashSet};
+use bigram::{Alphabet, BigramModel};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const CORPUS: [&str; 8] = ["emma", "olivia", "ava", "isabella", "sophia", "mia", "amelia", "harper"];
+
+fn train() -> BigramModel {
+    BigramModel::train_from_names(&CORPUS, 0.1).unwrap()
+}
+
+fn train_bundled() -> BigramModel {
+    let names: Vec<&str> = include_str!("../files/names.txt").split('\n').collect();
+    BigramModel::train_from_names(&names, 1.0).unwrap()
+}
+
+// share of the generated names with each key
+fn histogram<K: std::hash::Hash + Eq>(names: &[String], key: impl Fn(&str) -> K) -> HashMap<K, f64> {
+    let mut histogram = HashMap::new();
+    for name in names {
+        *histogram.entry(key(name)).or_insert(0.0) += 1.0 / names.len() as f64;
+    }
+    histogram
+}
+
+fn total_variation<K: std::hash::Hash + Eq>(a: &HashMap<K, f64>, b: &HashMap<K, f64>) -> f64 {
+    let keys: HashSet<&K> = a.keys().chain(b.keys()).collect();
+    keys.iter().map(|key| (a.get(key).unwrap_or(&0.0) - b.get(key).unwrap_or(&0.0)).abs()).sum::<f64>() / 2.0
+}
+
+fn sample(model: &BigramModel, seed: u64) -> Vec<String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..50_000).map(|_| model.sample_name(&mut rng)).collect()
+}
+
+#[test]
+fn dequantized_model_generates_a_close_distribution() {
+    let model = train_bundled();
+    let restored = BigramModel::from_quantized(&model.quantize()).unwrap();
+    let (before, after) = (sample(&model, 1), sample(&restored, 2));
+    // two samples of the same model already differ by about 0.01
+    let first_letters = total_variation(&histogram(&before, |name| name.chars().next()), &histogram(&after, |name| name.chars().next()));
+    assert!(first_letters < 0.03, "first letters differ by {first_letters}");
+    let lengths = total_variation(&histogram(&before, str::len), &histogram(&after, str::len));
+    assert!(lengths < 0.03, "lengths differ by {lengths}");
+}
+
+#[test]
+fn quantized_rows_stay_distributions_with_the_same_transitions() {
+    let model = train_bundled();
+    let restored = BigramModel::from_quantized(&model.quantize()).unwrap();
+    for (row, restored_row) in model.matrix.iter().zip(&restored.matrix) {
+        assert!((restored_row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        for (p, q) in row.iter().zip(restored_row) {
+            assert_eq!(*p > 0.0, *q > 0.0);
+            assert!((p - q).abs() < 0.01, "{p} became {q}");
+        }
+    }
+}
+
+#[test]
+fn quantized_file_keeps_the_alphabet_and_is_read_by_load() {
+    let alphabet = Alphabet::new('^', '$').unwrap().with_case(true);
+    let names: Vec<String> = ["Emma", "Ava"].iter().map(|n| format!(".{}.", n)).collect();
+    let model = BigramModel::from_names(&names, bigram::NameWeighting::Frequency, alphabet, 1.0).unwrap();
+    let path = std::env::temp_dir().join(format!("bigram-quantize-{}.q8", std::process::id()));
+    model.save_quantized(&path).unwrap();
+    let loaded = BigramModel::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.alphabet, alphabet);
+    assert_eq!(loaded.size(), model.size());
+}
+
+#[test]
+fn rare_transitions_stay_possible() {
+    // smoothing 0.1 over a tiny corpus leaves transitions far below 1/255 of the row maximum
+    let model = train();
+    let restored = BigramModel::from_quantized(&model.quantize()).unwrap();
+    assert!(restored.matrix.iter().flatten().zip(model.matrix.iter().flatten()).all(|(q, p)| (*q > 0.0) == (*p > 0.0)));
+}
+
+#[test]
+fn truncated_quantized_bytes_are_rejected() {
+    let bytes = train().quantize();
+    assert!(BigramModel::from_quantized(&bytes[..bytes.len() - 1]).is_err());
+    assert!(BigramModel::from_quantized(b"BGQ8").is_err());
+}
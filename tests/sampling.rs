@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use bigram::{BigramModel, SampleOptions, ALPHABET_SIZE, MAX_NAME_LEN};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn model_from_bigrams(bigrams: &[(&str, i32)]) -> BigramModel {
+    let counts: HashMap<String, i32> = bigrams.iter().map(|(b, c)| (b.to_string(), *c)).collect();
+    BigramModel::from_string_counts(&counts, 0.0)
+}
+
+#[test]
+fn sample_name_follows_the_only_path() {
+    let model = model_from_bigrams(&[(".a", 1), ("ab", 1), ("b.", 1)]);
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..10 {
+        assert_eq!(model.sample_name(&mut rng), "ab");
+    }
+}
+
+#[test]
+fn sample_name_strips_the_boundary() {
+    let model = BigramModel::train_from_names(&["emma", "olivia", "ava", "isabella", "sophia"], 1.0).unwrap();
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..100 {
+        let name = model.sample_name(&mut rng);
+        assert!(!name.is_empty());
+        assert!(name.chars().all(|c| c.is_ascii_lowercase()), "unexpected character in {name}");
+    }
+}
+
+fn boundary_heavy_model() -> BigramModel {
+    // the boundary row mostly leads straight back to the boundary, 'a' always ends the name
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    matrix[0][0] = 0.9;
+    matrix[0][1] = 0.1;
+    matrix[1][0] = 1.0;
+    BigramModel::from_matrix(matrix)
+}
+
+#[test]
+fn malformed_matrix_can_emit_an_empty_name() {
+    let model = boundary_heavy_model();
+    let mut rng = StdRng::seed_from_u64(3);
+    assert!((0..100).any(|_| model.sample_name(&mut rng).is_empty()));
+}
+
+#[test]
+fn sanitize_never_emits_an_empty_name() {
+    let model = boundary_heavy_model();
+    let options = SampleOptions { sanitize: true, ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..100 {
+        assert_eq!(model.sample_name_with(&mut rng, &options), "a");
+    }
+}
+
+#[test]
+fn sanitize_falls_back_to_letters_when_only_the_boundary_is_possible() {
+    let mut matrix = vec![vec![1.0 / ALPHABET_SIZE as f64; ALPHABET_SIZE]; ALPHABET_SIZE];
+    matrix[0] = vec![0.0; ALPHABET_SIZE];
+    matrix[0][0] = 1.0;
+    let model = BigramModel::from_matrix(matrix);
+    let options = SampleOptions { sanitize: true, ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(5);
+    for _ in 0..100 {
+        assert!(!model.sample_name_with(&mut rng, &options).is_empty());
+    }
+}
+
+#[test]
+fn generate_unique_never_repeats_a_name() {
+    let model = BigramModel::train_from_names(&["emma", "olivia", "ava", "isabella", "sophia"], 1.0).unwrap();
+    let mut rng = StdRng::seed_from_u64(11);
+    let batch = model.generate_unique(50, &mut rng);
+    assert_eq!(batch.len(), 50);
+    let distinct: std::collections::HashSet<&String> = batch.iter().collect();
+    assert_eq!(distinct.len(), batch.len());
+}
+
+#[test]
+fn generate_unique_is_cut_short_when_the_model_runs_out_of_names() {
+    // only "a" and "ab" can be generated
+    let model = model_from_bigrams(&[(".a", 2), ("a.", 1), ("ab", 1), ("b.", 1)]);
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut batch = model.generate_unique(5, &mut rng);
+    batch.sort();
+    assert_eq!(batch, vec!["a", "ab"]);
+}
+
+#[test]
+fn sample_with_affixes_keeps_both_ends() {
+    let model = BigramModel::train_from_names(&["emma", "olivia", "ava", "isabella", "sophia", "mia", "maya"], 1.0).unwrap();
+    let options = SampleOptions { prefix: "ma".to_string(), ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(4);
+    for _ in 0..20 {
+        let name = model.sample_with_affixes(&mut rng, &options, "a", 20).unwrap().unwrap();
+        assert!(name.starts_with("ma") && name.ends_with('a'), "unexpected name {name}");
+    }
+}
+
+#[test]
+fn sample_with_affixes_falls_back_to_the_most_likely_name() {
+    // "b" almost always ends the name, so sampling practically never reaches the "c" of the suffix
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    matrix[0][1] = 1.0;
+    matrix[1][2] = 1.0;
+    matrix[2][0] = 1.0 - 1e-9;
+    matrix[2][3] = 1e-9;
+    matrix[3][0] = 1.0;
+    let model = BigramModel::from_matrix(matrix);
+    let options = SampleOptions { prefix: "a".to_string(), ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(6);
+    assert_eq!(model.sample_with_affixes(&mut rng, &options, "bc", 5).unwrap().as_deref(), Some("abc"));
+    assert_eq!(model.sample_with_affixes(&mut rng, &options, "cb", 5).unwrap(), None);
+    assert!(model.sample_with_affixes(&mut rng, &options, "b1", 5).is_err());
+}
+
+#[test]
+fn most_likely_with_affixes_lets_the_ends_overlap() {
+    let model = model_from_bigrams(&[(".m", 1), ("ma", 1), ("a.", 3), ("ay", 1), ("ya", 1)]);
+    let (name, _) = model.most_likely_with_affixes("ma", "a", 10).unwrap().unwrap();
+    assert_eq!(name, "ma");
+}
+
+#[test]
+fn most_likely_with_affixes_rebuilds_long_middles() {
+    // the only name is bcda, so the middle between no prefix and the suffix is three letters long
+    let model = BigramModel::train_from_names(&["bcda"], 0.0).unwrap();
+    assert_eq!(model.most_likely_with_affixes("", "a", 10).unwrap(), Some(("bcda".to_string(), 0.0)));
+    assert_eq!(model.most_likely_with_affixes("b", "a", 10).unwrap().map(|(name, _)| name), Some("bcda".to_string()));
+}
+
+#[test]
+fn a_start_bias_of_zero_everywhere_falls_back_to_the_trained_row() {
+    let model = model_from_bigrams(&[(".a", 1), ("ab", 1), ("b.", 1)]);
+    let options = SampleOptions { start_bias: vec![('a', 0.0)], ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(2);
+    assert_eq!(model.sample_name_with(&mut rng, &options), "ab");
+}
+
+#[test]
+fn a_forbidden_row_ends_the_name() {
+    let mut model = model_from_bigrams(&[(".a", 1), ("ab", 1), ("b.", 1)]);
+    model.apply_constraints(&[('b', '.', 0.0)]).unwrap();
+    let mut rng = StdRng::seed_from_u64(3);
+    assert_eq!(model.sample_name(&mut rng), "ab");
+}
+
+#[test]
+fn names_that_never_reach_the_end_are_capped() {
+    let mut model = model_from_bigrams(&[(".a", 1), ("aa", 1), ("a.", 1)]);
+    model.apply_constraints(&[('a', '.', 0.0)]).unwrap();
+    let mut rng = StdRng::seed_from_u64(4);
+    assert_eq!(model.sample_name(&mut rng), "a".repeat(MAX_NAME_LEN));
+}
+
+#[test]
+fn min_step_prob_bans_rare_steps() {
+    // "a" is usually followed by the end, rarely by "b"
+    let model = model_from_bigrams(&[(".a", 1), ("a.", 99), ("ab", 1), ("b.", 1)]);
+    let options = SampleOptions { min_step_prob: 0.02, ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(8);
+    assert!((0..500).all(|_| model.sample_name_with(&mut rng, &options) == "a"));
+}
+
+#[test]
+fn min_step_prob_above_a_whole_row_samples_it_in_full() {
+    let model = model_from_bigrams(&[(".a", 1), (".b", 1), ("a.", 1), ("b.", 1)]);
+    let options = SampleOptions { min_step_prob: 0.9, ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(9);
+    let names: std::collections::HashSet<String> = (0..100).map(|_| model.sample_name_with(&mut rng, &options)).collect();
+    assert_eq!(names.len(), 2);
+}
+
+fn sampled_names(model: &BigramModel, options: &SampleOptions, seed: u64) -> std::collections::HashSet<String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..2000).map(|_| model.sample_name_with(&mut rng, options)).collect()
+}
+
+#[test]
+fn temperature_applies_before_top_p() {
+    // names start with "a", "b" or "c" 60%, 30% and 10% of the time
+    let model = model_from_bigrams(&[(".a", 6), (".b", 3), (".c", 1), ("a.", 1), ("b.", 1), ("c.", 1)]);
+    let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<std::collections::HashSet<String>>();
+    let top_p = SampleOptions { top_p: Some(0.75), ..Default::default() };
+    assert_eq!(sampled_names(&model, &top_p, 10), names(&["a", "b"]));
+    let temperature = SampleOptions { temperature: Some(0.5), ..Default::default() };
+    assert_eq!(sampled_names(&model, &temperature, 11), names(&["a", "b", "c"]));
+    // squaring the row leaves "a" with 78% of it, a nucleus of its own
+    let both = SampleOptions { temperature: Some(0.5), top_p: Some(0.75), ..Default::default() };
+    assert_eq!(sampled_names(&model, &both, 12), names(&["a"]));
+}
+
+#[test]
+fn high_temperature_flattens_the_row() {
+    let model = model_from_bigrams(&[(".a", 99), (".b", 1), ("a.", 1), ("b.", 1)]);
+    let options = SampleOptions { temperature: Some(100.0), ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(13);
+    let b = (0..2000).filter(|_| model.sample_name_with(&mut rng, &options) == "b").count();
+    // 99^(1/100) is about 1.05, so "b" comes up close to half of the time
+    assert!((800..1200).contains(&b), "b sampled {} times", b);
+}
+
+#[test]
+fn the_smallest_nucleus_keeps_the_most_likely_step() {
+    let model = model_from_bigrams(&[(".a", 2), (".b", 1), ("a.", 1), ("b.", 1)]);
+    let options = SampleOptions { top_p: Some(1e-9), ..Default::default() };
+    let mut rng = StdRng::seed_from_u64(14);
+    assert!((0..200).all(|_| model.sample_name_with(&mut rng, &options) == "a"));
+}
@@ -0,0 +1,79 @@
+use bigram::{char_to_int, clean_name, count_matrix, filter_to_alphabet, likelihood_of_word, log_likelihood_floored, log_likelihood_of_word, log_likelihood_with, score_word_floored, score_word_with, BigramModel, BoundaryMode};
+
+fn model() -> BigramModel {
+    BigramModel::train_from_names(&["zelda", "zoe", "ella", "linda"], 1.0).unwrap()
+}
+
+#[test]
+fn uppercase_input_is_cleaned_before_scoring() {
+    let model = model();
+    let expected = likelihood_of_word(".zelda.", &model.matrix);
+    assert!(expected > 0.0);
+    assert_eq!(likelihood_of_word("Zelda", &model.matrix), expected);
+    assert_eq!(likelihood_of_word("zelda", &model.matrix), expected);
+    assert_eq!(log_likelihood_of_word("Zelda", &model.matrix), log_likelihood_of_word(".zelda.", &model.matrix));
+}
+
+#[test]
+fn characters_outside_the_alphabet_have_zero_likelihood() {
+    let model = model();
+    assert_eq!(likelihood_of_word("zoé", &model.matrix), 0.0);
+    assert_eq!(log_likelihood_of_word("zoé", &model.matrix), f64::NEG_INFINITY);
+}
+
+#[test]
+fn names_outside_the_alphabet_are_dropped_before_counting() {
+    let names: Vec<String> = ["zoé", "zoe"].iter().map(|n| clean_name(n)).collect();
+    let (kept, dropped) = filter_to_alphabet(names);
+    assert_eq!(kept, vec![".zoe."]);
+    assert_eq!(dropped, 1);
+}
+
+#[test]
+fn epsilon_keeps_forbidden_transitions_finite() {
+    let mut model = model();
+    model.apply_constraints(&[('z', 'e', 0.0)]).unwrap();
+    assert_eq!(log_likelihood_with("zelda", &model.matrix, BoundaryMode::Inclusive), f64::NEG_INFINITY);
+
+    let epsilon = 1e-6;
+    let floored = log_likelihood_floored("zelda", &model.matrix, BoundaryMode::Inclusive, epsilon);
+    assert!(floored.is_finite());
+    // only the forbidden transition changes, it costs -ln(epsilon)
+    let rest: f64 = ["el", "ld", "da", "a."].iter().map(|bigram| {
+        let chars: Vec<usize> = bigram.chars().map(|c| char_to_int(c).unwrap()).collect();
+        model.matrix[chars[0]][chars[1]].ln()
+    }).sum::<f64>() + model.matrix[0][26].ln();
+    assert!((floored - (rest + epsilon.ln())).abs() < 1e-9);
+    assert!(score_word_floored("zelda", &model.matrix, BoundaryMode::Inclusive, epsilon).nll_per_char.is_finite());
+    // the matrix itself still forbids it
+    assert_eq!(model.matrix[26][5], 0.0);
+}
+
+#[test]
+fn smoothing_sensitivity_matches_a_finite_difference() {
+    let names: Vec<String> = ["zelda", "zoe", "ella", "linda"].iter().map(|n| clean_name(n)).collect();
+    let counts = count_matrix(&names);
+    let nll = |k: f64, word: &str| score_word_with(word, &BigramModel::from_counts(counts.clone(), k).matrix, BoundaryMode::Inclusive).nll_per_char;
+    let (k, h) = (0.5, 1e-6);
+    for word in ["ella", "zqx"] {
+        let (sensitivity, transitions) = BigramModel::from_counts(counts.clone(), k).smoothing_sensitivity(word, BoundaryMode::Inclusive);
+        let difference = (nll(k + h, word) - nll(k - h, word)) / (2.0 * h);
+        assert!((sensitivity - difference).abs() < 1e-6, "{word}: {sensitivity} against {difference}");
+        assert_eq!(transitions.len(), word.len() + 1);
+    }
+}
+
+#[test]
+fn unseen_transitions_lean_on_smoothing() {
+    // "lz" and "z." never occur, but l and z are seen as contexts
+    let (_, transitions) = model().smoothing_sensitivity("ellz", BoundaryMode::Inclusive);
+    let derivatives: Vec<f64> = transitions.iter().map(|(_, _, derivative)| *derivative).collect();
+    assert!(derivatives[..3].iter().all(|d| *d > 0.0), "{derivatives:?}");
+    assert!(derivatives[3..].iter().all(|d| *d < 0.0), "{derivatives:?}");
+}
+
+#[test]
+fn unseen_contexts_are_uniform_whatever_the_smoothing() {
+    let (sensitivity, _) = model().smoothing_sensitivity("qx", BoundaryMode::Interior);
+    assert_eq!(sensitivity, 0.0);
+}
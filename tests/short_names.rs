@@ -0,0 +1,52 @@
+use bigram::{clean_name, clean_names, count_bigrams, count_matrix, filter_by_length, log_likelihood_with, scored_bigrams, BigramModel, BoundaryMode};
+
+fn model() -> BigramModel {
+    BigramModel::from_counts(count_matrix(&clean_names(&["a".to_string(), "ab".to_string()])), 1.0)
+}
+
+#[test]
+fn empty_names_are_dropped_when_cleaning() {
+    let names: Vec<String> = ["", "a", "1234", " "].iter().map(|n| n.to_string()).collect();
+    assert_eq!(clean_names(&names), vec![".a."]);
+}
+
+#[test]
+fn single_letter_names_count_their_two_boundary_bigrams() {
+    let counts = count_matrix(&[clean_name("a")]);
+    assert_eq!(counts[0][1], 1.0);
+    assert_eq!(counts[1][0], 1.0);
+    assert_eq!(counts.iter().flatten().sum::<f64>(), 2.0);
+
+    let string_counts = count_bigrams(&[clean_name("a")]);
+    assert_eq!(string_counts.len(), 2);
+    assert_eq!(string_counts[".a"], 1);
+    assert_eq!(string_counts["a."], 1);
+}
+
+#[test]
+fn names_shorter_than_a_bigram_count_nothing() {
+    let names: Vec<String> = ["", "."].iter().map(|n| n.to_string()).collect();
+    assert_eq!(count_matrix(&names).iter().flatten().sum::<f64>(), 0.0);
+    assert!(count_bigrams(&names).is_empty());
+}
+
+#[test]
+fn short_words_are_scored_without_panicking() {
+    let model = model();
+    assert_eq!(scored_bigrams("a", BoundaryMode::Inclusive), 2);
+    assert_eq!(scored_bigrams("a", BoundaryMode::Interior), 0);
+    assert_eq!(scored_bigrams("", BoundaryMode::Inclusive), 1);
+    assert_eq!(scored_bigrams("", BoundaryMode::Interior), 0);
+    assert!(log_likelihood_with("a", &model.matrix, BoundaryMode::Inclusive).is_finite());
+    assert_eq!(log_likelihood_with("a", &model.matrix, BoundaryMode::Interior), 0.0);
+    // an empty word is only the boundary to boundary transition, which is never allowed
+    assert_eq!(log_likelihood_with("", &model.matrix, BoundaryMode::Inclusive), f64::NEG_INFINITY);
+    assert_eq!(log_likelihood_with("", &model.matrix, BoundaryMode::Interior), 0.0);
+}
+
+#[test]
+fn length_filter_measures_single_letters_as_one() {
+    let (kept, dropped) = filter_by_length(vec![clean_name("a"), clean_name("ab")], 2, 5);
+    assert_eq!(kept, vec![".ab."]);
+    assert_eq!(dropped, 1);
+}
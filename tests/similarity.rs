@@ -0,0 +1,25 @@
+use bigram::clean_names;
+use bigram::similarity::near_duplicate_clusters;
+
+fn cleaned(names: &[&str]) -> Vec<String> {
+    clean_names(&names.iter().map(|name| name.to_string()).collect::<Vec<String>>())
+}
+
+#[test]
+fn spelling_variants_cluster_under_the_most_common_one() {
+    let names = cleaned(&["Katharine", "Katherine", "Katherine", "Kathrine", "Emma", "Olivia"]);
+    assert_eq!(near_duplicate_clusters(&names, 1), vec![vec!["katherine", "katharine", "kathrine"]]);
+}
+
+#[test]
+fn names_only_cluster_with_the_same_first_letter() {
+    // "emma" and "gemma" are one insertion apart but start differently
+    assert!(near_duplicate_clusters(&cleaned(&["emma", "gemma", "ava"]), 1).is_empty());
+}
+
+#[test]
+fn clusters_do_not_chain_through_their_members() {
+    // "ann" is one edit from both, but "anna" and "any" are two apart
+    let clusters = near_duplicate_clusters(&cleaned(&["ann", "ann", "anna", "any", "annabel", "annabell"]), 1);
+    assert_eq!(clusters, vec![vec!["ann", "anna", "any"], vec!["annabel", "annabell"]]);
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "unicode")]
+
+use bigram::{clean_name, clean_name_with, CleanOptions};
+
+fn normalizing() -> CleanOptions {
+    CleanOptions { normalize_unicode: true, ..CleanOptions::default() }
+}
+
+#[test]
+fn decomposed_accents_are_composed_before_cleaning() {
+    // "zoe" followed by a combining acute accent
+    let decomposed = "Zoe\u{301}";
+    let cleaned = clean_name_with(decomposed, &normalizing());
+    assert_eq!(cleaned, ".zo\u{e9}.");
+    assert_eq!(cleaned.chars().count(), 5);
+    assert_eq!(cleaned, clean_name("Zo\u{e9}"));
+}
+
+#[test]
+fn composed_input_is_unchanged() {
+    assert_eq!(clean_name_with("Zo\u{e9}", &normalizing()), clean_name("Zo\u{e9}"));
+    assert_eq!(clean_name_with("emma", &normalizing()), ".emma.");
+}
@@ -0,0 +1,55 @@
+use bigram::{score_word, score_word_with, BigramModel, BoundaryMode, ALPHABET_SIZE};
+
+// a tiny model over "a" and "b" with easy probabilities
+fn model() -> BigramModel {
+    let mut matrix = vec![vec![0.0; ALPHABET_SIZE]; ALPHABET_SIZE];
+    matrix[0][1] = 0.5;
+    matrix[0][2] = 0.5;
+    matrix[1][0] = 0.25;
+    matrix[1][2] = 0.75;
+    matrix[2][0] = 1.0;
+    BigramModel::from_matrix(matrix)
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-12, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn scores_include_the_boundary_by_default() {
+    let model = model();
+    // .a, ab, b. = 0.5 * 0.75 * 1.0
+    let score = score_word("ab", &model.matrix);
+    assert_eq!(score.bigrams, 3);
+    assert_close(score.log_probability, 0.375f64.ln());
+    assert_close(score.nll_per_char, -0.375f64.ln() / 3.0);
+    assert_close(score.nll_per_char_log10, -0.375f64.log10() / 3.0);
+}
+
+#[test]
+fn interior_scores_only_count_the_inner_bigrams() {
+    let model = model();
+    let score = score_word_with("ab", &model.matrix, BoundaryMode::Interior);
+    assert_eq!(score.bigrams, 1);
+    assert_close(score.log_probability, 0.75f64.ln());
+    assert_close(score.nll_per_char, -0.75f64.ln());
+    assert_close(score.nll_per_char_log10, -0.75f64.log10());
+}
+
+#[test]
+fn words_with_nothing_to_score_are_zero() {
+    let model = model();
+    let score = score_word_with("a", &model.matrix, BoundaryMode::Interior);
+    assert_eq!(score.bigrams, 0);
+    assert_eq!(score.log_probability, 0.0);
+    assert_eq!(score.nll_per_char, 0.0);
+}
+
+#[test]
+fn impossible_words_score_infinity() {
+    let model = model();
+    // "ba" has zero probability
+    let score = score_word("ba", &model.matrix);
+    assert_eq!(score.log_probability, f64::NEG_INFINITY);
+    assert_eq!(score.nll_per_char, f64::INFINITY);
+}